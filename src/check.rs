@@ -0,0 +1,132 @@
+use async_trait::async_trait;
+use serde_json::{Map, Value};
+
+use crate::result::{HttpInfo, Status, TlsInfo};
+use std::fmt;
+use std::net::SocketAddr;
+use std::time::{Duration, Instant};
+use tokio::net::TcpStream;
+
+/// What a [`Check`] knows about the target it runs against.
+#[derive(Debug, Clone)]
+pub struct CheckContext<'a> {
+    /// Host name as given in the target, without the port (used for SNI, Host headers, ...).
+    pub host: &'a str,
+    /// Address the stream is connected to.
+    pub peer: SocketAddr,
+    /// Time budget for the check. The prober gives up shortly after it, so
+    /// checks that can say more than "timeout" should enforce it themselves.
+    pub timeout: Duration,
+    /// When the check started, right after the TCP handshake. Checks measure
+    /// [`CheckOutcome::first_byte`] from here.
+    pub started: Instant,
+}
+
+/// Protocol-level health check run on the stream after the TCP handshake.
+///
+/// A check that returns `Ok` marks the target healthy; `Err` marks it failed
+/// with the error message. Custom implementations are reported exactly like
+/// the built-in ones.
+///
+/// ```no_run
+/// use async_trait::async_trait;
+/// use tcp_probe::{Check, CheckContext, CheckError, CheckOutcome};
+/// use tokio::io::AsyncReadExt;
+/// use tokio::net::TcpStream;
+///
+/// struct NonEmptyGreeting;
+///
+/// #[async_trait]
+/// impl Check for NonEmptyGreeting {
+///     fn name(&self) -> &str {
+///         "greeting"
+///     }
+///
+///     async fn run(&self, stream: &mut TcpStream, _ctx: &CheckContext<'_>) -> Result<CheckOutcome, CheckError> {
+///         let mut buf = [0u8; 64];
+///         match stream.read(&mut buf).await? {
+///             0 => Err(CheckError::new("connection closed before greeting")),
+///             n => Ok(CheckOutcome::new().detail("greeting_bytes", n)),
+///         }
+///     }
+/// }
+/// ```
+#[async_trait]
+pub trait Check: Send + Sync {
+    /// Short name reported in results, e.g. `"redis"`.
+    fn name(&self) -> &str;
+
+    async fn run(
+        &self,
+        stream: &mut TcpStream,
+        ctx: &CheckContext<'_>,
+    ) -> Result<CheckOutcome, CheckError>;
+}
+
+/// Data a successful check wants reported alongside the result.
+#[derive(Debug, Clone, Default)]
+pub struct CheckOutcome {
+    /// Marks the target as [`Status::Warn`](crate::Status::Warn) with this message.
+    pub warning: Option<String>,
+    /// Greeting sent by the server, escaped for display.
+    pub banner: Option<String>,
+    pub tls: Option<TlsInfo>,
+    pub http: Option<HttpInfo>,
+    /// Time from [`CheckContext::started`] until the server's first byte of response arrived.
+    pub first_byte: Option<Duration>,
+    pub details: Map<String, Value>,
+}
+
+impl CheckOutcome {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Attach a key/value pair to the result's `details`.
+    pub fn detail(mut self, key: impl Into<String>, value: impl Into<Value>) -> Self {
+        self.details.insert(key.into(), value.into());
+        self
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct CheckError {
+    message: String,
+    status: Status,
+}
+
+impl CheckError {
+    pub fn new(message: impl Into<String>) -> Self {
+        CheckError {
+            message: message.into(),
+            status: Status::Fail,
+        }
+    }
+
+    /// The service answered but is still starting up (loading data, in recovery, ...).
+    pub fn starting(message: impl Into<String>) -> Self {
+        CheckError {
+            message: message.into(),
+            status: Status::Starting,
+        }
+    }
+
+    /// Status the target is reported with: [`Status::Fail`] or [`Status::Starting`].
+    pub fn status(&self) -> Status {
+        self.status
+    }
+}
+
+impl fmt::Display for CheckError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for CheckError {}
+
+impl From<std::io::Error> for CheckError {
+    fn from(e: std::io::Error) -> Self {
+        CheckError::new(e.to_string())
+    }
+}
@@ -0,0 +1,105 @@
+use async_trait::async_trait;
+use std::time::Duration;
+use tokio::io::AsyncReadExt;
+use tokio::net::TcpStream;
+use tokio::time::{timeout, timeout_at, Instant};
+
+use crate::check::{Check, CheckContext, CheckError, CheckOutcome};
+use crate::escape::escape_bytes;
+
+/// Once some data has arrived, how long to wait for the rest of the greeting.
+const BANNER_IDLE: Duration = Duration::from_millis(100);
+
+/// Captures the greeting a server sends right after the connect.
+///
+/// A server that stays silent is still healthy; the result simply has no banner.
+#[derive(Debug, Clone)]
+pub struct Banner {
+    max_bytes: usize,
+    window: Duration,
+}
+
+impl Default for Banner {
+    fn default() -> Self {
+        Banner {
+            max_bytes: 256,
+            window: Duration::from_secs(2),
+        }
+    }
+}
+
+impl Banner {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Maximum number of bytes to capture.
+    pub fn max_bytes(mut self, max_bytes: usize) -> Self {
+        self.max_bytes = max_bytes.max(1);
+        self
+    }
+
+    /// How long to wait for the server to start talking.
+    pub fn window(mut self, window: Duration) -> Self {
+        self.window = window;
+        self
+    }
+}
+
+#[async_trait]
+impl Check for Banner {
+    fn name(&self) -> &str {
+        "banner"
+    }
+
+    async fn run(
+        &self,
+        stream: &mut TcpStream,
+        ctx: &CheckContext<'_>,
+    ) -> Result<CheckOutcome, CheckError> {
+        let deadline = Instant::now() + self.window.min(ctx.timeout);
+        let (banner, first_byte) = read_banner(stream, self.max_bytes, deadline).await?;
+
+        let mut outcome = CheckOutcome::new();
+        outcome.first_byte = first_byte.map(|at| at.into_std() - ctx.started);
+        if !banner.is_empty() {
+            outcome.banner = Some(escape_bytes(banner.trim_ascii_end()));
+        }
+        Ok(outcome)
+    }
+}
+
+/// Read up to `max_bytes` of greeting, stopping early at a line end or once the peer goes quiet.
+///
+/// Also returns when the first byte arrived, if any did.
+pub(crate) async fn read_banner(
+    stream: &mut TcpStream,
+    max_bytes: usize,
+    deadline: Instant,
+) -> std::io::Result<(Vec<u8>, Option<Instant>)> {
+    let mut banner = Vec::new();
+    let mut first_byte = None;
+    let mut buf = vec![0u8; max_bytes];
+
+    while banner.len() < max_bytes {
+        let want = max_bytes - banner.len();
+        let read = if banner.is_empty() {
+            timeout_at(deadline, stream.read(&mut buf[..want])).await
+        } else {
+            timeout(BANNER_IDLE, stream.read(&mut buf[..want])).await
+        };
+        match read {
+            Ok(Ok(0)) | Err(_) => break,
+            Ok(Ok(n)) => {
+                first_byte.get_or_insert_with(Instant::now);
+                banner.extend_from_slice(&buf[..n]);
+                if buf[n - 1] == b'\n' {
+                    break;
+                }
+            }
+            Ok(Err(e)) => return Err(e),
+        }
+    }
+
+    Ok((banner, first_byte))
+}
@@ -0,0 +1,353 @@
+use async_trait::async_trait;
+use std::ops::RangeInclusive;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::net::TcpStream;
+use tokio::time::{timeout_at, Instant};
+
+use crate::check::{Check, CheckContext, CheckError, CheckOutcome};
+use crate::checks::Tls;
+use crate::escape::escape_bytes;
+use crate::result::HttpInfo;
+
+/// Largest response body kept for assertions; anything beyond is ignored.
+const MAX_BODY: usize = 1024 * 1024;
+
+/// Sends an HTTP/1.1 request over the probed connection and asserts on the response.
+///
+/// With [`tls`](Http::tls) set the request goes over HTTPS, and the TLS
+/// session is reported just like the [`Tls`] check does.
+#[derive(Debug, Clone)]
+pub struct Http {
+    method: String,
+    path: String,
+    tls: Option<Tls>,
+    statuses: Vec<RangeInclusive<u16>>,
+    headers: Vec<(String, Option<String>)>,
+    body_contains: Vec<String>,
+    json: Vec<(String, Option<String>)>,
+}
+
+impl Default for Http {
+    fn default() -> Self {
+        Http {
+            method: "GET".to_string(),
+            path: "/".to_string(),
+            tls: None,
+            statuses: Vec::new(),
+            headers: Vec::new(),
+            body_contains: Vec::new(),
+            json: Vec::new(),
+        }
+    }
+}
+
+impl Http {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn method(mut self, method: impl Into<String>) -> Self {
+        self.method = method.into();
+        self
+    }
+
+    /// Request path including any query string.
+    pub fn path(mut self, path: impl Into<String>) -> Self {
+        self.path = path.into();
+        self
+    }
+
+    /// Speak HTTPS using this TLS configuration.
+    pub fn tls(mut self, tls: Tls) -> Self {
+        self.tls = Some(tls);
+        self
+    }
+
+    /// Accept these status codes; defaults to 200-399 when none are given.
+    pub fn expect_status(mut self, range: RangeInclusive<u16>) -> Self {
+        self.statuses.push(range);
+        self
+    }
+
+    /// Require a response header, optionally containing `value`.
+    pub fn require_header(mut self, name: impl Into<String>, value: Option<String>) -> Self {
+        self.headers.push((name.into(), value));
+        self
+    }
+
+    /// Require the body to contain `needle`.
+    pub fn body_contains(mut self, needle: impl Into<String>) -> Self {
+        self.body_contains.push(needle.into());
+        self
+    }
+
+    /// Require the JSON body to have a value at `pointer` (RFC 6901), optionally equal to `expected`.
+    pub fn json_pointer(mut self, pointer: impl Into<String>, expected: Option<String>) -> Self {
+        self.json.push((pointer.into(), expected));
+        self
+    }
+
+    async fn exchange<S>(
+        &self,
+        stream: &mut S,
+        ctx: &CheckContext<'_>,
+        deadline: Instant,
+    ) -> Result<(HttpInfo, Instant), CheckError>
+    where
+        S: AsyncRead + AsyncWrite + Unpin,
+    {
+        let default_port = if self.tls.is_some() { 443 } else { 80 };
+        let host = if ctx.host.contains(':') {
+            format!("[{}]", ctx.host)
+        } else {
+            ctx.host.to_string()
+        };
+        let host = match ctx.peer.port() {
+            port if port == default_port => host,
+            port => format!("{}:{}", host, port),
+        };
+        let request = format!(
+            "{} {} HTTP/1.1\r\nHost: {}\r\nUser-Agent: tcp-probe/{}\r\nAccept: */*\r\nConnection: close\r\n\r\n",
+            self.method,
+            self.path,
+            host,
+            env!("CARGO_PKG_VERSION")
+        );
+
+        let start = Instant::now();
+        stream.write_all(request.as_bytes()).await?;
+        stream.flush().await?;
+
+        let mut buf = Vec::new();
+        let mut chunk = [0u8; 8192];
+        let mut first_byte = None;
+
+        let (status, headers, header_len) = loop {
+            let n = read_until(stream, &mut chunk, deadline).await?;
+            if n == 0 {
+                return Err(CheckError::new("connection closed before response headers"));
+            }
+            first_byte.get_or_insert_with(Instant::now);
+            buf.extend_from_slice(&chunk[..n]);
+
+            let mut parsed = [httparse::EMPTY_HEADER; 64];
+            let mut response = httparse::Response::new(&mut parsed);
+            match response.parse(&buf) {
+                Ok(httparse::Status::Complete(len)) => {
+                    let headers: Vec<(String, String)> = response
+                        .headers
+                        .iter()
+                        .map(|h| {
+                            let value = String::from_utf8_lossy(h.value).trim().to_string();
+                            (h.name.to_ascii_lowercase(), value)
+                        })
+                        .collect();
+                    break (response.code.unwrap_or(0), headers, len);
+                }
+                Ok(httparse::Status::Partial) if buf.len() < MAX_BODY => {}
+                Ok(httparse::Status::Partial) => {
+                    return Err(CheckError::new("response headers too large"))
+                }
+                Err(e) => {
+                    let shown = &buf[..buf.len().min(80)];
+                    return Err(CheckError::new(format!(
+                        "invalid HTTP response ({}): \"{}\"",
+                        e,
+                        escape_bytes(shown)
+                    )));
+                }
+            }
+        };
+
+        let header = |name: &str| {
+            headers
+                .iter()
+                .find(|(n, _)| n == name)
+                .map(|(_, v)| v.as_str())
+        };
+        let chunked = header("transfer-encoding")
+            .is_some_and(|te| te.to_ascii_lowercase().contains("chunked"));
+        let content_length = header("content-length").and_then(|v| v.parse::<usize>().ok());
+        let has_body = self.method != "HEAD"
+            && status != 204
+            && status != 304
+            && !(100..200).contains(&status);
+
+        let mut body = buf.split_off(header_len);
+        if has_body {
+            loop {
+                let complete = match content_length {
+                    _ if chunked => body.ends_with(b"0\r\n\r\n"),
+                    Some(len) => body.len() >= len,
+                    None => false,
+                };
+                if complete || body.len() >= MAX_BODY {
+                    break;
+                }
+                match read_until(stream, &mut chunk, deadline).await? {
+                    0 => break,
+                    n => body.extend_from_slice(&chunk[..n]),
+                }
+            }
+        } else {
+            body.clear();
+        }
+        if chunked {
+            body = decode_chunked(&body);
+        }
+        if let Some(len) = content_length {
+            body.truncate(len);
+        }
+
+        // Set by the header loop, which only exits after reading data.
+        let first_byte = first_byte.unwrap_or(start);
+        let info = HttpInfo {
+            status,
+            ttfb_ms: (first_byte - start).as_secs_f64() * 1000.0,
+            total_ms: start.elapsed().as_secs_f64() * 1000.0,
+            body_bytes: body.len(),
+        };
+        self.assert_response(&info, &headers, &body)?;
+        Ok((info, first_byte))
+    }
+
+    fn assert_response(
+        &self,
+        info: &HttpInfo,
+        headers: &[(String, String)],
+        body: &[u8],
+    ) -> Result<(), CheckError> {
+        let status_ok = if self.statuses.is_empty() {
+            (200..=399).contains(&info.status)
+        } else {
+            self.statuses.iter().any(|r| r.contains(&info.status))
+        };
+        if !status_ok {
+            return Err(CheckError::new(format!(
+                "unexpected HTTP status {}",
+                info.status
+            )));
+        }
+
+        for (name, expected) in &self.headers {
+            let lower = name.to_ascii_lowercase();
+            let Some((_, value)) = headers.iter().find(|(n, _)| *n == lower) else {
+                return Err(CheckError::new(format!("missing header '{}'", name)));
+            };
+            if let Some(expected) = expected {
+                if !value.contains(expected.as_str()) {
+                    return Err(CheckError::new(format!(
+                        "header '{}' is \"{}\", expected \"{}\"",
+                        name, value, expected
+                    )));
+                }
+            }
+        }
+
+        let text = String::from_utf8_lossy(body);
+        for needle in &self.body_contains {
+            if !text.contains(needle.as_str()) {
+                return Err(CheckError::new(format!(
+                    "body does not contain \"{}\"",
+                    needle
+                )));
+            }
+        }
+
+        if !self.json.is_empty() {
+            let doc: serde_json::Value = serde_json::from_slice(body)
+                .map_err(|e| CheckError::new(format!("body is not valid JSON: {}", e)))?;
+            for (pointer, expected) in &self.json {
+                let Some(value) = doc.pointer(pointer) else {
+                    return Err(CheckError::new(format!(
+                        "JSON body has no value at {}",
+                        pointer
+                    )));
+                };
+                let actual = match value {
+                    serde_json::Value::String(s) => s.clone(),
+                    other => other.to_string(),
+                };
+                if let Some(expected) = expected {
+                    if actual != *expected {
+                        return Err(CheckError::new(format!(
+                            "JSON {} is {}, expected {}",
+                            pointer, actual, expected
+                        )));
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl Check for Http {
+    fn name(&self) -> &str {
+        if self.tls.is_some() {
+            "https"
+        } else {
+            "http"
+        }
+    }
+
+    async fn run(
+        &self,
+        stream: &mut TcpStream,
+        ctx: &CheckContext<'_>,
+    ) -> Result<CheckOutcome, CheckError> {
+        let deadline = Instant::now() + ctx.timeout;
+        let mut outcome = CheckOutcome::new();
+
+        let (info, first_byte) = match &self.tls {
+            Some(tls) => {
+                let (mut stream, tls_info) = tls.handshake(stream, ctx.host).await?;
+                outcome.warning = tls.assess(&tls_info)?;
+                outcome.tls = Some(tls_info);
+                self.exchange(&mut stream, ctx, deadline).await?
+            }
+            None => self.exchange(stream, ctx, deadline).await?,
+        };
+
+        outcome.http = Some(info);
+        outcome.first_byte = Some(first_byte.into_std() - ctx.started);
+        Ok(outcome)
+    }
+}
+
+async fn read_until<S>(
+    stream: &mut S,
+    buf: &mut [u8],
+    deadline: Instant,
+) -> Result<usize, CheckError>
+where
+    S: AsyncRead + Unpin,
+{
+    match timeout_at(deadline, stream.read(buf)).await {
+        Ok(Ok(n)) => Ok(n),
+        Ok(Err(e)) => Err(e.into()),
+        Err(_) => Err(CheckError::new("timed out waiting for HTTP response")),
+    }
+}
+
+/// Decode a `Transfer-Encoding: chunked` body, keeping whatever arrived intact.
+fn decode_chunked(mut raw: &[u8]) -> Vec<u8> {
+    let mut body = Vec::new();
+    while let Some(line_end) = raw.windows(2).position(|w| w == b"\r\n") {
+        let size_field = String::from_utf8_lossy(&raw[..line_end]);
+        let size_hex = size_field.split(';').next().unwrap_or("").trim();
+        let Ok(size) = usize::from_str_radix(size_hex, 16) else {
+            break;
+        };
+        raw = &raw[line_end + 2..];
+        if size == 0 {
+            break;
+        }
+        let take = size.min(raw.len());
+        body.extend_from_slice(&raw[..take]);
+        raw = raw.get(take + 2..).unwrap_or_default();
+    }
+    body
+}
@@ -0,0 +1,21 @@
+//! Built-in [`Check`](crate::Check) implementations.
+
+mod banner;
+mod http;
+mod mysql;
+mod postgres;
+mod redis;
+mod send_expect;
+mod smtp;
+mod ssh;
+mod tls;
+
+pub use banner::Banner;
+pub use http::Http;
+pub use mysql::Mysql;
+pub use postgres::Postgres;
+pub use redis::{Redis, RedisRole};
+pub use send_expect::SendExpect;
+pub use smtp::Smtp;
+pub use ssh::Ssh;
+pub use tls::Tls;
@@ -0,0 +1,139 @@
+use async_trait::async_trait;
+use tokio::io::AsyncReadExt;
+use tokio::net::TcpStream;
+
+use crate::check::{Check, CheckContext, CheckError, CheckOutcome};
+use crate::escape::escape_bytes;
+
+/// Largest greeting accepted; real ones are around a hundred bytes.
+const MAX_HANDSHAKE: usize = 4096;
+
+/// Capability flags worth naming in the report.
+const CAPABILITIES: &[(u32, &str)] = &[
+    (0x0000_0200, "PROTOCOL_41"),
+    (0x0000_0800, "SSL"),
+    (0x0000_8000, "SECURE_CONNECTION"),
+    (0x0001_0000, "MULTI_STATEMENTS"),
+    (0x0008_0000, "PLUGIN_AUTH"),
+    (0x0010_0000, "CONNECT_ATTRS"),
+    (0x0020_0000, "PLUGIN_AUTH_LENENC_CLIENT_DATA"),
+    (0x0080_0000, "SESSION_TRACK"),
+    (0x0100_0000, "DEPRECATE_EOF"),
+];
+
+/// Reads the MySQL/MariaDB initial handshake packet.
+///
+/// An `ERR` packet in place of the greeting (for example a host blocked
+/// after too many connection errors) fails the target with the server's message.
+#[derive(Debug, Clone, Default)]
+pub struct Mysql;
+
+impl Mysql {
+    pub fn new() -> Self {
+        Mysql
+    }
+}
+
+#[async_trait]
+impl Check for Mysql {
+    fn name(&self) -> &str {
+        "mysql"
+    }
+
+    async fn run(
+        &self,
+        stream: &mut TcpStream,
+        ctx: &CheckContext<'_>,
+    ) -> Result<CheckOutcome, CheckError> {
+        let mut header = [0u8; 4];
+        stream
+            .read_exact(&mut header)
+            .await
+            .map_err(|e| CheckError::new(format!("no handshake packet: {}", e)))?;
+        let first_byte = ctx.started.elapsed();
+        let len = u32::from_le_bytes([header[0], header[1], header[2], 0]) as usize;
+        if len > MAX_HANDSHAKE {
+            return Err(CheckError::new(format!(
+                "not a MySQL handshake (packet of {} bytes, starts \"{}\")",
+                len,
+                escape_bytes(&header)
+            )));
+        }
+        let mut payload = vec![0u8; len];
+        stream.read_exact(&mut payload).await?;
+
+        let mut outcome = match payload.first() {
+            Some(0xff) => return Err(parse_error(&payload)),
+            Some(_) => parse_handshake(&payload)?,
+            None => return Err(CheckError::new("empty handshake packet")),
+        };
+        outcome.first_byte = Some(first_byte);
+        Ok(outcome)
+    }
+}
+
+fn parse_error(payload: &[u8]) -> CheckError {
+    let code = payload
+        .get(1..3)
+        .map(|c| u16::from_le_bytes([c[0], c[1]]))
+        .unwrap_or(0);
+    let mut message = payload.get(3..).unwrap_or_default();
+    // Protocol 4.1 servers put "#" and a five character SQLSTATE before the text.
+    if message.first() == Some(&b'#') && message.len() >= 6 {
+        message = &message[6..];
+    }
+    CheckError::new(format!(
+        "ERR {}: {}",
+        code,
+        String::from_utf8_lossy(message)
+    ))
+}
+
+fn parse_handshake(payload: &[u8]) -> Result<CheckOutcome, CheckError> {
+    let truncated = || CheckError::new("truncated handshake packet");
+
+    let protocol = payload[0];
+    if protocol != 10 && protocol != 9 {
+        return Err(CheckError::new(format!(
+            "not a MySQL handshake (protocol version {}, starts \"{}\")",
+            protocol,
+            escape_bytes(&payload[..payload.len().min(16)])
+        )));
+    }
+
+    let rest = &payload[1..];
+    let nul = rest.iter().position(|&b| b == 0).ok_or_else(truncated)?;
+    let server_version = String::from_utf8_lossy(&rest[..nul]).into_owned();
+    let rest = &rest[nul + 1..];
+
+    let mut outcome = CheckOutcome::new()
+        .detail("protocol_version", protocol)
+        .detail("server_version", server_version.as_str());
+
+    // connection id (4), auth-plugin-data part 1 (8), filler (1), capabilities low (2)
+    if protocol == 10 {
+        let connection_id = rest
+            .get(..4)
+            .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+            .ok_or_else(truncated)?;
+        let low = rest.get(13..15).ok_or_else(truncated)?;
+        let mut capabilities = u16::from_le_bytes([low[0], low[1]]) as u32;
+        // charset (1), status flags (2), capabilities high (2)
+        if let Some(high) = rest.get(18..20) {
+            capabilities |= (u16::from_le_bytes([high[0], high[1]]) as u32) << 16;
+        }
+
+        let names: Vec<&str> = CAPABILITIES
+            .iter()
+            .filter(|(flag, _)| capabilities & flag != 0)
+            .map(|(_, name)| *name)
+            .collect();
+        outcome = outcome
+            .detail("connection_id", connection_id)
+            .detail("capabilities", format!("0x{:08x}", capabilities))
+            .detail("capability_flags", names);
+    }
+
+    outcome.banner = Some(escape_bytes(server_version.as_bytes()));
+    Ok(outcome)
+}
@@ -0,0 +1,205 @@
+use async_trait::async_trait;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::net::TcpStream;
+
+use crate::check::{Check, CheckContext, CheckError, CheckOutcome};
+use crate::checks::Tls;
+
+/// `SSLRequest` code from the frontend/backend protocol.
+const SSL_REQUEST: i32 = 80877103;
+/// Protocol version 3.0.
+const PROTOCOL_V3: i32 = 196608;
+/// Largest backend message accepted while waiting for the startup reply.
+const MAX_MESSAGE: usize = 64 * 1024;
+
+/// Speaks the PostgreSQL wire protocol far enough to prove the server responds.
+///
+/// An `SSLRequest` is always sent. With a [`user`](Postgres::user) set, a
+/// `StartupMessage` follows and the reply tells apart a server that is
+/// accepting connections (it asks for authentication, or rejects the
+/// credentials) from one that is still starting up or in recovery, which is
+/// reported as [`Status::Starting`](crate::Status::Starting).
+#[derive(Debug, Clone)]
+pub struct Postgres {
+    user: Option<String>,
+    database: Option<String>,
+    tls: Tls,
+}
+
+impl Default for Postgres {
+    fn default() -> Self {
+        Postgres {
+            user: None,
+            database: None,
+            tls: Tls::new().insecure(true),
+        }
+    }
+}
+
+impl Postgres {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Send a `StartupMessage` for this user after the `SSLRequest`.
+    pub fn user(mut self, user: impl Into<String>) -> Self {
+        self.user = Some(user.into());
+        self
+    }
+
+    /// Database named in the `StartupMessage`; the server defaults it to the user name.
+    pub fn database(mut self, database: impl Into<String>) -> Self {
+        self.database = Some(database.into());
+        self
+    }
+
+    /// TLS settings used when the server accepts the `SSLRequest`.
+    /// Certificates are not verified by default.
+    pub fn tls(mut self, tls: Tls) -> Self {
+        self.tls = tls;
+        self
+    }
+
+    async fn startup<S>(&self, stream: &mut S, user: &str) -> Result<&'static str, CheckError>
+    where
+        S: AsyncRead + AsyncWrite + Unpin,
+    {
+        let mut params = Vec::new();
+        for (key, value) in [("user", Some(user)), ("database", self.database.as_deref())] {
+            if let Some(value) = value {
+                params.extend_from_slice(key.as_bytes());
+                params.push(0);
+                params.extend_from_slice(value.as_bytes());
+                params.push(0);
+            }
+        }
+        params.extend_from_slice(b"application_name\0tcp-probe\0\0");
+
+        let mut message = Vec::with_capacity(params.len() + 8);
+        message.extend_from_slice(&(params.len() as i32 + 8).to_be_bytes());
+        message.extend_from_slice(&PROTOCOL_V3.to_be_bytes());
+        message.extend_from_slice(&params);
+        stream.write_all(&message).await?;
+        stream.flush().await?;
+
+        let (tag, body) = read_message(stream).await?;
+        match tag {
+            b'R' => Ok("accepting"),
+            b'E' => {
+                let error = ServerError::parse(&body);
+                match error.code.as_str() {
+                    // cannot_connect_now: starting up, shutting down or in recovery.
+                    "57P03" => Err(CheckError::starting(error.to_string())),
+                    // Authentication or unknown-database errors come from a server that accepts connections.
+                    code if code.starts_with("28") || code == "3D000" => Ok("accepting"),
+                    _ => Err(CheckError::new(error.to_string())),
+                }
+            }
+            other => Err(CheckError::new(format!(
+                "unexpected startup reply '{}'",
+                other.escape_ascii()
+            ))),
+        }
+    }
+}
+
+#[async_trait]
+impl Check for Postgres {
+    fn name(&self) -> &str {
+        "postgres"
+    }
+
+    async fn run(
+        &self,
+        stream: &mut TcpStream,
+        ctx: &CheckContext<'_>,
+    ) -> Result<CheckOutcome, CheckError> {
+        let mut request = [0u8; 8];
+        request[..4].copy_from_slice(&8i32.to_be_bytes());
+        request[4..].copy_from_slice(&SSL_REQUEST.to_be_bytes());
+        stream.write_all(&request).await?;
+
+        let mut reply = [0u8; 1];
+        if stream.read(&mut reply).await? == 0 {
+            return Err(CheckError::new("connection closed after SSLRequest"));
+        }
+        let first_byte = ctx.started.elapsed();
+        let ssl = match reply[0] {
+            b'S' => true,
+            b'N' => false,
+            b'E' => return Err(CheckError::new("server rejected SSLRequest")),
+            other => {
+                return Err(CheckError::new(format!(
+                    "not a PostgreSQL server (SSLRequest reply '{}')",
+                    other.escape_ascii()
+                )))
+            }
+        };
+
+        let mut outcome = CheckOutcome::new().detail("ssl", ssl);
+        outcome.first_byte = Some(first_byte);
+        let Some(user) = &self.user else {
+            return Ok(outcome.detail("state", "responding"));
+        };
+
+        let state = if ssl {
+            let (mut tls, info) = self.tls.handshake(stream, ctx.host).await?;
+            outcome.tls = Some(info);
+            self.startup(&mut tls, user).await?
+        } else {
+            self.startup(stream, user).await?
+        };
+        Ok(outcome.detail("state", state))
+    }
+}
+
+async fn read_message<S>(stream: &mut S) -> Result<(u8, Vec<u8>), CheckError>
+where
+    S: AsyncRead + Unpin,
+{
+    let mut header = [0u8; 5];
+    stream.read_exact(&mut header).await?;
+    let len = i32::from_be_bytes([header[1], header[2], header[3], header[4]]);
+    let len = len
+        .checked_sub(4)
+        .and_then(|body| usize::try_from(body).ok())
+        .filter(|&len| len <= MAX_MESSAGE)
+        .ok_or_else(|| CheckError::new(format!("invalid message length {}", len)))?;
+
+    let mut body = vec![0u8; len];
+    stream.read_exact(&mut body).await?;
+    Ok((header[0], body))
+}
+
+/// The fields of an `ErrorResponse` worth reporting.
+struct ServerError {
+    severity: String,
+    code: String,
+    message: String,
+}
+
+impl ServerError {
+    fn parse(body: &[u8]) -> Self {
+        let mut error = ServerError {
+            severity: "ERROR".to_string(),
+            code: String::new(),
+            message: String::new(),
+        };
+        for field in body.split(|&b| b == 0).filter(|f| !f.is_empty()) {
+            let value = String::from_utf8_lossy(&field[1..]).into_owned();
+            match field[0] {
+                b'S' => error.severity = value,
+                b'C' => error.code = value,
+                b'M' => error.message = value,
+                _ => {}
+            }
+        }
+        error
+    }
+}
+
+impl std::fmt::Display for ServerError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} {}: {}", self.severity, self.code, self.message)
+    }
+}
@@ -0,0 +1,211 @@
+use async_trait::async_trait;
+use std::fmt;
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::net::TcpStream;
+
+use crate::check::{Check, CheckContext, CheckError, CheckOutcome};
+use crate::escape::escape_bytes;
+
+/// Longest status, error or length line accepted from the server.
+const MAX_LINE: u64 = 4096;
+/// Largest bulk reply accepted, e.g. for `INFO replication`.
+const MAX_MESSAGE: usize = 64 * 1024;
+
+/// Replication role a Redis server is expected to have.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RedisRole {
+    Master,
+    Replica,
+}
+
+/// Sends `PING` (after an optional `AUTH`) and expects `+PONG`.
+///
+/// A server still loading its dataset is reported as
+/// [`Status::Starting`](crate::Status::Starting).
+///
+/// With a [`role`](Redis::role) set, `INFO replication` is checked as well;
+/// replicas must also report `master_link_status:up`.
+#[derive(Debug, Clone, Default)]
+pub struct Redis {
+    username: Option<String>,
+    password: Option<String>,
+    role: Option<RedisRole>,
+}
+
+impl Redis {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Authenticate with `AUTH [username] password` before pinging.
+    pub fn auth(mut self, username: Option<String>, password: impl Into<String>) -> Self {
+        self.username = username;
+        self.password = Some(password.into());
+        self
+    }
+
+    pub fn role(mut self, role: RedisRole) -> Self {
+        self.role = Some(role);
+        self
+    }
+}
+
+#[async_trait]
+impl Check for Redis {
+    fn name(&self) -> &str {
+        "redis"
+    }
+
+    async fn run(
+        &self,
+        stream: &mut TcpStream,
+        ctx: &CheckContext<'_>,
+    ) -> Result<CheckOutcome, CheckError> {
+        let mut conn = BufReader::new(stream);
+        let mut first_byte = None;
+
+        if let Some(password) = &self.password {
+            let mut auth = vec!["AUTH"];
+            auth.extend(self.username.as_deref());
+            auth.push(password);
+            let reply = command(&mut conn, &auth).await?;
+            first_byte = Some(ctx.started.elapsed());
+            match reply {
+                Reply::Simple(_) => {}
+                Reply::Error(e) => return Err(CheckError::new(format!("AUTH failed: {}", e))),
+                other => return Err(unexpected("AUTH", &other)),
+            }
+        }
+
+        // A server still loading its dataset answers -LOADING here.
+        let reply = command(&mut conn, &["PING"]).await?;
+        first_byte.get_or_insert_with(|| ctx.started.elapsed());
+        match reply {
+            Reply::Simple(s) if s == "PONG" => {}
+            Reply::Error(e) if e.starts_with("LOADING") => return Err(CheckError::starting(e)),
+            Reply::Error(e) => return Err(CheckError::new(e)),
+            other => return Err(unexpected("PING", &other)),
+        }
+
+        let mut outcome = CheckOutcome::new();
+        outcome.first_byte = first_byte;
+        let Some(expected) = self.role else {
+            return Ok(outcome);
+        };
+
+        let info = match command(&mut conn, &["INFO", "replication"]).await? {
+            Reply::Bulk(info) => info,
+            Reply::Error(e) => return Err(CheckError::new(format!("INFO failed: {}", e))),
+            other => return Err(unexpected("INFO", &other)),
+        };
+        let field = |name: &str| {
+            info.lines()
+                .find_map(|line| line.strip_prefix(name)?.strip_prefix(':'))
+                .map(str::trim)
+        };
+
+        let role = field("role").unwrap_or("unknown");
+        outcome = outcome.detail("role", role);
+        let role_matches = match expected {
+            RedisRole::Master => role == "master",
+            RedisRole::Replica => role == "slave" || role == "replica",
+        };
+        if !role_matches {
+            let wanted = match expected {
+                RedisRole::Master => "master",
+                RedisRole::Replica => "replica",
+            };
+            return Err(CheckError::new(format!(
+                "role is {}, expected {}",
+                role, wanted
+            )));
+        }
+
+        if expected == RedisRole::Replica {
+            let link = field("master_link_status").unwrap_or("unknown");
+            outcome = outcome.detail("master_link_status", link);
+            if let Some(master) = field("master_host") {
+                outcome = outcome.detail("master_host", master);
+            }
+            if link != "up" {
+                return Err(CheckError::new(format!("replication link is {}", link)));
+            }
+        } else if let Some(replicas) = field("connected_slaves").and_then(|n| n.parse::<u64>().ok())
+        {
+            outcome = outcome.detail("connected_replicas", replicas);
+        }
+
+        Ok(outcome)
+    }
+}
+
+#[derive(Debug)]
+enum Reply {
+    Simple(String),
+    Error(String),
+    Bulk(String),
+    Other(String),
+}
+
+impl fmt::Display for Reply {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Reply::Simple(s) => write!(f, "+{}", s),
+            Reply::Error(e) => write!(f, "-{}", e),
+            Reply::Bulk(b) => write!(f, "${} bytes", b.len()),
+            Reply::Other(o) => f.write_str(o),
+        }
+    }
+}
+
+fn unexpected(command: &str, reply: &Reply) -> CheckError {
+    CheckError::new(format!("unexpected reply to {}: {}", command, reply))
+}
+
+async fn command(conn: &mut BufReader<&mut TcpStream>, args: &[&str]) -> Result<Reply, CheckError> {
+    let mut request = format!("*{}\r\n", args.len());
+    for arg in args {
+        request.push_str(&format!("${}\r\n{}\r\n", arg.len(), arg));
+    }
+    conn.get_mut().write_all(request.as_bytes()).await?;
+
+    let mut line = Vec::new();
+    if (&mut *conn)
+        .take(MAX_LINE)
+        .read_until(b'\n', &mut line)
+        .await?
+        == 0
+    {
+        return Err(CheckError::new("connection closed"));
+    }
+    if !line.ends_with(b"\n") && line.len() as u64 == MAX_LINE {
+        return Err(CheckError::new(format!(
+            "reply line longer than {} bytes",
+            MAX_LINE
+        )));
+    }
+    let text = String::from_utf8_lossy(line.trim_ascii_end()).into_owned();
+
+    let reply = match text.as_bytes().first() {
+        Some(b'+') => Reply::Simple(text[1..].to_string()),
+        Some(b'-') => Reply::Error(text[1..].to_string()),
+        Some(b'$') => {
+            let len: i64 = text[1..]
+                .parse()
+                .map_err(|_| CheckError::new(format!("bad bulk length: {}", text)))?;
+            if len < 0 {
+                return Ok(Reply::Other("(nil)".to_string()));
+            }
+            let len = usize::try_from(len)
+                .ok()
+                .filter(|&len| len <= MAX_MESSAGE)
+                .ok_or_else(|| CheckError::new(format!("bulk reply too large: {}", text)))?;
+            let mut body = vec![0u8; len + 2];
+            conn.read_exact(&mut body).await?;
+            body.truncate(len);
+            Reply::Bulk(String::from_utf8_lossy(&body).into_owned())
+        }
+        _ => Reply::Other(escape_bytes(&line)),
+    };
+    Ok(reply)
+}
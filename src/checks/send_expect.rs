@@ -0,0 +1,96 @@
+use async_trait::async_trait;
+use regex::bytes::Regex;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+use tokio::time::{timeout_at, Instant};
+
+use crate::check::{Check, CheckContext, CheckError, CheckOutcome};
+use crate::escape::escape_bytes;
+
+/// Largest response buffered while waiting for `expect` to match.
+const MAX_RESPONSE: usize = 64 * 1024;
+
+/// Writes an optional payload, then reads until a regex matches (like Nagios `check_tcp`).
+#[derive(Debug, Clone, Default)]
+pub struct SendExpect {
+    send: Option<Vec<u8>>,
+    expect: Option<Regex>,
+}
+
+impl SendExpect {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Raw bytes written right after connecting.
+    pub fn send(mut self, payload: impl Into<Vec<u8>>) -> Self {
+        self.send = Some(payload.into());
+        self
+    }
+
+    /// Pattern the response must match; without one the check only writes.
+    pub fn expect(mut self, pattern: Regex) -> Self {
+        self.expect = Some(pattern);
+        self
+    }
+}
+
+#[async_trait]
+impl Check for SendExpect {
+    fn name(&self) -> &str {
+        "send-expect"
+    }
+
+    async fn run(
+        &self,
+        stream: &mut TcpStream,
+        ctx: &CheckContext<'_>,
+    ) -> Result<CheckOutcome, CheckError> {
+        if let Some(payload) = &self.send {
+            stream.write_all(payload).await?;
+            stream.flush().await?;
+        }
+
+        let Some(expect) = &self.expect else {
+            return Ok(CheckOutcome::new());
+        };
+
+        let deadline = Instant::now() + ctx.timeout;
+        let mut response = Vec::new();
+        let mut buf = [0u8; 4096];
+        let mut first_byte = None;
+        loop {
+            if let Some(m) = expect.find(&response) {
+                let mut outcome = CheckOutcome::new().detail("matched", escape_bytes(m.as_bytes()));
+                outcome.first_byte = first_byte;
+                return Ok(outcome);
+            }
+            if response.len() >= MAX_RESPONSE {
+                return Err(mismatch(expect, &response, "response too large"));
+            }
+
+            match timeout_at(deadline, stream.read(&mut buf)).await {
+                Ok(Ok(0)) => return Err(mismatch(expect, &response, "connection closed")),
+                Ok(Ok(n)) => {
+                    first_byte.get_or_insert_with(|| ctx.started.elapsed());
+                    response.extend_from_slice(&buf[..n]);
+                }
+                Ok(Err(e)) => return Err(e.into()),
+                Err(_) => {
+                    let reason = format!("read timeout after {}ms", ctx.timeout.as_millis());
+                    return Err(mismatch(expect, &response, &reason));
+                }
+            }
+        }
+    }
+}
+
+fn mismatch(expect: &Regex, response: &[u8], reason: &str) -> CheckError {
+    let shown = &response[..response.len().min(120)];
+    CheckError::new(format!(
+        "no match for /{}/ ({}), got \"{}\"",
+        expect.as_str(),
+        reason,
+        escape_bytes(shown)
+    ))
+}
@@ -0,0 +1,161 @@
+use async_trait::async_trait;
+use tokio::io::{AsyncBufRead, AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::net::TcpStream;
+
+use crate::check::{Check, CheckContext, CheckError, CheckOutcome};
+use crate::checks::Tls;
+use crate::escape::escape_bytes;
+
+/// Longest reply line accepted (RFC 5321 allows 512 bytes).
+const MAX_LINE: u64 = 1024;
+
+/// Reads the SMTP greeting, sends `EHLO` and reports the advertised extensions.
+///
+/// Only a `220` greeting counts as healthy; `421` and `554` greetings fail
+/// the target even though the TCP connect worked. With
+/// [`starttls`](Smtp::starttls) set, the check also upgrades the session and
+/// validates the certificate like the [`Tls`] check does.
+#[derive(Debug, Clone)]
+pub struct Smtp {
+    helo_name: String,
+    starttls: Option<Tls>,
+}
+
+impl Default for Smtp {
+    fn default() -> Self {
+        Smtp {
+            helo_name: "localhost".to_string(),
+            starttls: None,
+        }
+    }
+}
+
+impl Smtp {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Name sent with `EHLO`.
+    pub fn helo_name(mut self, name: impl Into<String>) -> Self {
+        self.helo_name = name.into();
+        self
+    }
+
+    /// Run `STARTTLS` after `EHLO` and complete the handshake with this configuration.
+    pub fn starttls(mut self, tls: Tls) -> Self {
+        self.starttls = Some(tls);
+        self
+    }
+}
+
+#[async_trait]
+impl Check for Smtp {
+    fn name(&self) -> &str {
+        "smtp"
+    }
+
+    async fn run(
+        &self,
+        stream: &mut TcpStream,
+        ctx: &CheckContext<'_>,
+    ) -> Result<CheckOutcome, CheckError> {
+        let mut conn = BufReader::new(stream);
+
+        let greeting = read_reply(&mut conn).await?;
+        let first_byte = ctx.started.elapsed();
+        if greeting.code != 220 {
+            return Err(CheckError::new(format!("greeting {}", greeting)));
+        }
+        let mut outcome = CheckOutcome::new();
+        outcome.first_byte = Some(first_byte);
+        outcome.banner = Some(escape_bytes(greeting.lines.join(" ").as_bytes()));
+
+        conn.get_mut()
+            .write_all(format!("EHLO {}\r\n", self.helo_name).as_bytes())
+            .await?;
+        let ehlo = read_reply(&mut conn).await?;
+        if ehlo.code != 250 {
+            return Err(CheckError::new(format!("EHLO rejected: {}", ehlo)));
+        }
+        let extensions: Vec<String> = ehlo.lines.iter().skip(1).cloned().collect();
+        let has_starttls = extensions
+            .iter()
+            .any(|e| e.eq_ignore_ascii_case("STARTTLS"));
+        outcome = outcome.detail("extensions", extensions);
+
+        let Some(tls) = &self.starttls else {
+            let _ = conn.get_mut().write_all(b"QUIT\r\n").await;
+            return Ok(outcome);
+        };
+
+        if !has_starttls {
+            return Err(CheckError::new("server does not advertise STARTTLS"));
+        }
+        conn.get_mut().write_all(b"STARTTLS\r\n").await?;
+        let ready = read_reply(&mut conn).await?;
+        if ready.code != 220 {
+            return Err(CheckError::new(format!("STARTTLS rejected: {}", ready)));
+        }
+
+        let (mut session, info) = tls.handshake(conn.into_inner(), ctx.host).await?;
+        outcome.warning = tls.assess(&info)?;
+        outcome.tls = Some(info);
+        let _ = session.write_all(b"QUIT\r\n").await;
+        Ok(outcome)
+    }
+}
+
+struct Reply {
+    code: u16,
+    /// Text of each line, without the code and separator.
+    lines: Vec<String>,
+}
+
+impl std::fmt::Display for Reply {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} {}", self.code, self.lines.join(" "))
+    }
+}
+
+/// Read a possibly multi-line reply (`250-...` continued until `250 ...`).
+async fn read_reply<R>(conn: &mut R) -> Result<Reply, CheckError>
+where
+    R: AsyncBufRead + Unpin,
+{
+    let mut lines = Vec::new();
+
+    loop {
+        let mut line = Vec::new();
+        if (&mut *conn)
+            .take(MAX_LINE)
+            .read_until(b'\n', &mut line)
+            .await?
+            == 0
+        {
+            return Err(CheckError::new("connection closed mid-reply"));
+        }
+        if !line.ends_with(b"\n") && line.len() as u64 == MAX_LINE {
+            return Err(CheckError::new(format!(
+                "reply line too long (over {} bytes)",
+                MAX_LINE
+            )));
+        }
+        let line = String::from_utf8_lossy(line.trim_ascii_end()).into_owned();
+
+        let parsed = line.get(..3).and_then(|c| c.parse::<u16>().ok());
+        let Some(line_code) = parsed else {
+            return Err(CheckError::new(format!(
+                "not an SMTP reply: \"{}\"",
+                escape_bytes(line.as_bytes())
+            )));
+        };
+        lines.push(line.get(4..).unwrap_or_default().to_string());
+
+        if line.as_bytes().get(3) != Some(&b'-') {
+            return Ok(Reply {
+                code: line_code,
+                lines,
+            });
+        }
+    }
+}
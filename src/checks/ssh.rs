@@ -0,0 +1,148 @@
+use async_trait::async_trait;
+use regex::Regex;
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, BufReader};
+use tokio::net::TcpStream;
+use tokio::time::{timeout_at, Instant};
+
+use crate::check::{Check, CheckContext, CheckError, CheckOutcome};
+use crate::escape::escape_bytes;
+
+/// RFC 4253 caps the identification line at 255 bytes including CR LF.
+const MAX_LINE: u64 = 255;
+/// Lines a server may send before its identification string.
+const MAX_PRELUDE_LINES: usize = 16;
+
+/// Reads the `SSH-2.0-...` identification line and optionally asserts on it.
+#[derive(Debug, Clone, Default)]
+pub struct Ssh {
+    pattern: Option<Regex>,
+    min_openssh: Option<(String, Vec<u32>)>,
+}
+
+impl Ssh {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Require the identification line to match `pattern`.
+    pub fn expect(mut self, pattern: Regex) -> Self {
+        self.pattern = Some(pattern);
+        self
+    }
+
+    /// Require an OpenSSH server of at least this version, e.g. `"8.9"` or `"9.3p2"`.
+    pub fn min_openssh(mut self, version: &str) -> Result<Self, String> {
+        let parsed = parse_openssh_version(version)
+            .ok_or_else(|| format!("invalid OpenSSH version '{}'", version))?;
+        self.min_openssh = Some((version.to_string(), parsed));
+        Ok(self)
+    }
+}
+
+#[async_trait]
+impl Check for Ssh {
+    fn name(&self) -> &str {
+        "ssh"
+    }
+
+    async fn run(
+        &self,
+        stream: &mut TcpStream,
+        ctx: &CheckContext<'_>,
+    ) -> Result<CheckOutcome, CheckError> {
+        let deadline = Instant::now() + ctx.timeout;
+        let mut reader = BufReader::new(stream);
+        let mut ident = None;
+        let mut first_byte = None;
+
+        for _ in 0..=MAX_PRELUDE_LINES {
+            let mut line = Vec::new();
+            let mut limited = (&mut reader).take(MAX_LINE);
+            match timeout_at(deadline, limited.read_until(b'\n', &mut line)).await {
+                Ok(Ok(0)) => return Err(CheckError::new("connection closed before SSH banner")),
+                Ok(Ok(_)) => {
+                    first_byte.get_or_insert_with(|| ctx.started.elapsed());
+                }
+                Ok(Err(e)) => return Err(e.into()),
+                Err(_) => {
+                    return Err(CheckError::new(format!(
+                        "no SSH banner within {}ms",
+                        ctx.timeout.as_millis()
+                    )))
+                }
+            }
+            if line.starts_with(b"SSH-") {
+                ident = Some(line);
+                break;
+            }
+        }
+
+        let ident = ident.ok_or_else(|| CheckError::new("no SSH identification line"))?;
+        let ident = String::from_utf8_lossy(ident.trim_ascii_end()).into_owned();
+
+        // SSH-protoversion-softwareversion SP comments
+        let rest = &ident["SSH-".len()..];
+        let (proto, software) = rest.split_once('-').unwrap_or((rest, ""));
+        let (software, comments) = match software.split_once(' ') {
+            Some((software, comments)) => (software, Some(comments)),
+            None => (software, None),
+        };
+
+        let mut outcome = CheckOutcome::new()
+            .detail("protocol", proto)
+            .detail("software", software);
+        if let Some(comments) = comments {
+            outcome = outcome.detail("comments", comments);
+        }
+        outcome.banner = Some(escape_bytes(ident.as_bytes()));
+        outcome.first_byte = first_byte;
+
+        if proto != "2.0" && proto != "1.99" {
+            return Err(CheckError::new(format!(
+                "unsupported SSH protocol '{}'",
+                proto
+            )));
+        }
+        if let Some(pattern) = &self.pattern {
+            if !pattern.is_match(&ident) {
+                return Err(CheckError::new(format!(
+                    "banner \"{}\" does not match /{}/",
+                    escape_bytes(ident.as_bytes()),
+                    pattern.as_str()
+                )));
+            }
+        }
+        if let Some((min_name, min)) = &self.min_openssh {
+            let version = software
+                .strip_prefix("OpenSSH_")
+                .and_then(parse_openssh_version)
+                .ok_or_else(|| CheckError::new(format!("not an OpenSSH server: {}", software)))?;
+            if version < *min {
+                return Err(CheckError::new(format!(
+                    "{} is older than OpenSSH {}",
+                    software, min_name
+                )));
+            }
+        }
+
+        Ok(outcome)
+    }
+}
+
+/// `9.6p1` -> `[9, 6, 1]`; a missing portable release counts as `p0`.
+fn parse_openssh_version(s: &str) -> Option<Vec<u32>> {
+    let (version, portable) = match s.split_once('p') {
+        Some((version, portable)) => (version, portable),
+        None => (s, "0"),
+    };
+    let mut parts = version
+        .split('.')
+        .map(|p| p.parse().ok())
+        .collect::<Option<Vec<u32>>>()?;
+    if parts.len() < 2 {
+        parts.resize(2, 0);
+    }
+    let portable: String = portable.chars().take_while(char::is_ascii_digit).collect();
+    parts.push(portable.parse().unwrap_or(0));
+    Some(parts)
+}
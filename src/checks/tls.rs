@@ -0,0 +1,268 @@
+use async_trait::async_trait;
+use std::sync::Arc;
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+use time::format_description::well_known::Rfc3339;
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio::net::TcpStream;
+use tokio_rustls::client::TlsStream;
+use tokio_rustls::rustls::client::danger::{
+    HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier,
+};
+use tokio_rustls::rustls::crypto::{self, CryptoProvider};
+use tokio_rustls::rustls::pki_types::{CertificateDer, ServerName, UnixTime};
+use tokio_rustls::rustls::{ClientConfig, DigitallySignedStruct, RootCertStore, SignatureScheme};
+use tokio_rustls::TlsConnector;
+use x509_parser::extensions::GeneralName;
+
+use crate::check::{Check, CheckContext, CheckError, CheckOutcome};
+use crate::result::TlsInfo;
+
+/// Completes a TLS handshake and reports the session and leaf certificate.
+///
+/// The chain is verified against the Mozilla root store unless
+/// [`insecure`](Tls::insecure) is set. Certificates close to expiry can be
+/// turned into warnings or failures with the `cert_*_days` thresholds.
+#[derive(Debug, Clone)]
+pub struct Tls {
+    config: Arc<ClientConfig>,
+    insecure: bool,
+    alpn: Vec<String>,
+    warn_days: Option<i64>,
+    fail_days: Option<i64>,
+}
+
+impl Default for Tls {
+    fn default() -> Self {
+        Tls {
+            config: client_config(false, &[]),
+            insecure: false,
+            alpn: Vec::new(),
+            warn_days: None,
+            fail_days: None,
+        }
+    }
+}
+
+impl Tls {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Skip certificate chain and hostname verification.
+    pub fn insecure(mut self, insecure: bool) -> Self {
+        self.insecure = insecure;
+        self.config = client_config(self.insecure, &self.alpn);
+        self
+    }
+
+    /// ALPN protocols to offer, e.g. `["h2", "http/1.1"]`.
+    pub fn alpn<I, S>(mut self, protocols: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.alpn = protocols.into_iter().map(Into::into).collect();
+        self.config = client_config(self.insecure, &self.alpn);
+        self
+    }
+
+    /// Warn when the leaf certificate expires within this many days.
+    pub fn cert_warn_days(mut self, days: u32) -> Self {
+        self.warn_days = Some(days.into());
+        self
+    }
+
+    /// Fail when the leaf certificate expires within this many days.
+    pub fn cert_fail_days(mut self, days: u32) -> Self {
+        self.fail_days = Some(days.into());
+        self
+    }
+
+    /// Run the handshake over `stream`, returning the encrypted stream and what was negotiated.
+    pub(crate) async fn handshake<S>(
+        &self,
+        stream: S,
+        host: &str,
+    ) -> Result<(TlsStream<S>, TlsInfo), CheckError>
+    where
+        S: AsyncRead + AsyncWrite + Unpin,
+    {
+        let server_name = ServerName::try_from(host.to_string())
+            .map_err(|e| CheckError::new(format!("invalid TLS server name '{}': {}", host, e)))?;
+
+        let start = Instant::now();
+        let tls = TlsConnector::from(self.config.clone())
+            .connect(server_name, stream)
+            .await
+            .map_err(|e| CheckError::new(format!("TLS handshake failed: {}", e)))?;
+        let handshake_ms = start.elapsed().as_secs_f64() * 1000.0;
+
+        let (_, session) = tls.get_ref();
+        let mut info = TlsInfo {
+            handshake_ms,
+            version: session
+                .protocol_version()
+                .and_then(|v| v.as_str())
+                .map(|v| v.replace("TLSv1_", "TLSv1."))
+                .unwrap_or_else(|| "unknown".to_string()),
+            cipher: session
+                .negotiated_cipher_suite()
+                .and_then(|c| c.suite().as_str())
+                .unwrap_or("unknown")
+                .to_string(),
+            alpn: session
+                .alpn_protocol()
+                .map(|p| String::from_utf8_lossy(p).into_owned()),
+            subject: None,
+            sans: Vec::new(),
+            not_after: None,
+            days_left: None,
+        };
+        if let Some(leaf) = session.peer_certificates().and_then(|c| c.first()) {
+            describe_certificate(leaf, &mut info);
+        }
+
+        Ok((tls, info))
+    }
+
+    /// Apply the expiry thresholds, returning a warning message if one applies.
+    pub(crate) fn assess(&self, info: &TlsInfo) -> Result<Option<String>, CheckError> {
+        let Some(days_left) = info.days_left else {
+            return Ok(None);
+        };
+        let expiry = format!(
+            "certificate expires in {} days ({})",
+            days_left,
+            info.not_after.as_deref().unwrap_or("unknown")
+        );
+
+        if self.fail_days.is_some_and(|days| days_left <= days) {
+            return Err(CheckError::new(expiry));
+        }
+        if self.warn_days.is_some_and(|days| days_left <= days) {
+            return Ok(Some(expiry));
+        }
+        Ok(None)
+    }
+}
+
+#[async_trait]
+impl Check for Tls {
+    fn name(&self) -> &str {
+        "tls"
+    }
+
+    async fn run(
+        &self,
+        stream: &mut TcpStream,
+        ctx: &CheckContext<'_>,
+    ) -> Result<CheckOutcome, CheckError> {
+        let (_, info) = self.handshake(stream, ctx.host).await?;
+        let mut outcome = CheckOutcome::new();
+        outcome.warning = self.assess(&info)?;
+        outcome.tls = Some(info);
+        Ok(outcome)
+    }
+}
+
+fn client_config(insecure: bool, alpn: &[String]) -> Arc<ClientConfig> {
+    let provider = Arc::new(crypto::ring::default_provider());
+    let builder = ClientConfig::builder_with_provider(provider.clone())
+        .with_safe_default_protocol_versions()
+        .expect("ring provider supports the default protocol versions");
+
+    let mut config = if insecure {
+        builder
+            .dangerous()
+            .with_custom_certificate_verifier(Arc::new(NoVerification(provider)))
+            .with_no_client_auth()
+    } else {
+        let mut roots = RootCertStore::empty();
+        roots.extend(webpki_roots::TLS_SERVER_ROOTS.iter().cloned());
+        builder.with_root_certificates(roots).with_no_client_auth()
+    };
+    config.alpn_protocols = alpn.iter().map(|p| p.as_bytes().to_vec()).collect();
+    Arc::new(config)
+}
+
+fn describe_certificate(der: &CertificateDer<'_>, info: &mut TlsInfo) {
+    let Ok((_, cert)) = x509_parser::parse_x509_certificate(der) else {
+        return;
+    };
+
+    info.subject = Some(cert.subject().to_string());
+    if let Ok(Some(san)) = cert.subject_alternative_name() {
+        for name in &san.value.general_names {
+            match name {
+                GeneralName::DNSName(dns) => info.sans.push(dns.to_string()),
+                GeneralName::IPAddress(ip) => info.sans.push(format_ip(ip)),
+                _ => {}
+            }
+        }
+    }
+
+    let not_after = cert.validity().not_after;
+    info.not_after = not_after.to_datetime().format(&Rfc3339).ok();
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0);
+    info.days_left = Some((not_after.timestamp() - now).div_euclid(86_400));
+}
+
+fn format_ip(bytes: &[u8]) -> String {
+    match bytes.len() {
+        4 => std::net::Ipv4Addr::from(<[u8; 4]>::try_from(bytes).unwrap()).to_string(),
+        16 => std::net::Ipv6Addr::from(<[u8; 16]>::try_from(bytes).unwrap()).to_string(),
+        _ => crate::escape::escape_bytes(bytes),
+    }
+}
+
+/// Accepts any certificate while still checking handshake signatures.
+#[derive(Debug)]
+struct NoVerification(Arc<CryptoProvider>);
+
+impl ServerCertVerifier for NoVerification {
+    fn verify_server_cert(
+        &self,
+        _end_entity: &CertificateDer<'_>,
+        _intermediates: &[CertificateDer<'_>],
+        _server_name: &ServerName<'_>,
+        _ocsp_response: &[u8],
+        _now: UnixTime,
+    ) -> Result<ServerCertVerified, tokio_rustls::rustls::Error> {
+        Ok(ServerCertVerified::assertion())
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, tokio_rustls::rustls::Error> {
+        crypto::verify_tls12_signature(
+            message,
+            cert,
+            dss,
+            &self.0.signature_verification_algorithms,
+        )
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, tokio_rustls::rustls::Error> {
+        crypto::verify_tls13_signature(
+            message,
+            cert,
+            dss,
+            &self.0.signature_verification_algorithms,
+        )
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.0.signature_verification_algorithms.supported_schemes()
+    }
+}
@@ -0,0 +1,347 @@
+//! Target lists with per-target settings: TOML and YAML config files, and the
+//! line-based format read by `--file`.
+//!
+//! ```toml
+//! [defaults]
+//! timeout = "3s"
+//! tags = ["prod"]
+//!
+//! [[targets]]
+//! addr = "db.internal:5432"
+//! check = "postgres"
+//! retries = 2
+//! description = "primary database"
+//! ```
+//!
+//! The line-based format takes the same settings as trailing options:
+//!
+//! ```text
+//! include common.txt
+//! db.internal:5432 timeout=2s retries=3 tags=db,prod check=postgres
+//! ```
+//!
+//! Everything is validated while parsing, so a bad value is reported with the
+//! line (and column, for TOML and YAML) it came from before anything is probed.
+
+use clap::ValueEnum;
+use serde::{Deserialize, Deserializer};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tcp_probe::duration;
+
+use crate::CheckKind;
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    /// Settings for every target that does not set its own.
+    #[serde(default)]
+    pub defaults: Defaults,
+    #[serde(default)]
+    pub targets: Vec<TargetConfig>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Defaults {
+    #[serde(default, deserialize_with = "duration")]
+    pub timeout: Option<Duration>,
+    pub retries: Option<u32>,
+    pub check: Option<CheckKind>,
+    /// Added to every target's own tags.
+    #[serde(default)]
+    pub tags: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TargetConfig {
+    /// Anything accepted on the command line: host:port, a port list, a host
+    /// pattern, `srv:` or a URL.
+    #[serde(deserialize_with = "addr")]
+    pub addr: String,
+    #[serde(default, deserialize_with = "duration")]
+    pub timeout: Option<Duration>,
+    pub retries: Option<u32>,
+    pub check: Option<CheckKind>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    pub description: Option<String>,
+}
+
+impl Config {
+    /// Read `path`, picking the format from its extension.
+    pub fn load(path: &str) -> Result<Config, String> {
+        let content =
+            std::fs::read_to_string(path).map_err(|e| format!("cannot read {}: {}", path, e))?;
+        let extension = Path::new(path).extension().and_then(|e| e.to_str());
+        match extension {
+            Some("toml") => toml::from_str(&content).map_err(|e| format!("{}: {}", path, e)),
+            Some("yaml" | "yml") => {
+                serde_yaml::from_str(&content).map_err(|e| format!("{}: {}", path, e))
+            }
+            _ => Err(format!(
+                "{}: config files must end in .toml, .yaml or .yml",
+                path
+            )),
+        }
+    }
+}
+
+/// Read a targets file: one target per line, optionally followed by
+/// `key=value` options, with `#` comments and `include other.txt` lines
+/// resolved relative to the including file.
+pub fn read_targets_file(path: &str) -> Result<Vec<TargetConfig>, String> {
+    let mut targets = Vec::new();
+    read_lines(Path::new(path), &mut Vec::new(), &mut targets)?;
+    Ok(targets)
+}
+
+/// `including` holds the files currently being read, to catch include cycles.
+fn read_lines(
+    path: &Path,
+    including: &mut Vec<PathBuf>,
+    targets: &mut Vec<TargetConfig>,
+) -> Result<(), String> {
+    let cannot_read = |e: std::io::Error| format!("cannot read {}: {}", path.display(), e);
+    let canonical = path.canonicalize().map_err(cannot_read)?;
+    if including.contains(&canonical) {
+        return Err(format!("{} includes itself", path.display()));
+    }
+    let content = std::fs::read_to_string(path).map_err(cannot_read)?;
+
+    including.push(canonical);
+    for (n, line) in content.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let at_line = |e: String| format!("{}:{}: {}", path.display(), n + 1, e);
+        match line.split_once(char::is_whitespace) {
+            Some(("include", file)) => {
+                let file = path.parent().unwrap_or(Path::new(".")).join(file.trim());
+                read_lines(&file, including, targets).map_err(at_line)?;
+            }
+            _ => targets.push(parse_line(line).map_err(at_line)?),
+        }
+    }
+    including.pop();
+    Ok(())
+}
+
+/// `addr key=value ...`; values may be double-quoted to include spaces.
+fn parse_line(line: &str) -> Result<TargetConfig, String> {
+    let mut words = split_words(line)?.into_iter();
+    let addr = words.next().unwrap_or_default();
+    crate::check_addr(&addr)?;
+    let mut target = TargetConfig {
+        addr,
+        timeout: None,
+        retries: None,
+        check: None,
+        tags: Vec::new(),
+        description: None,
+    };
+
+    for option in words {
+        let (key, value) = option
+            .split_once('=')
+            .ok_or_else(|| format!("expected key=value, got '{}'", option))?;
+        match key {
+            "timeout" => target.timeout = Some(duration::parse(value)?),
+            "retries" => {
+                let retries = value
+                    .parse()
+                    .map_err(|_| format!("invalid retries '{}'", value))?;
+                target.retries = Some(retries);
+            }
+            "check" => {
+                let check = CheckKind::from_str(value, true).map_err(|_| {
+                    let names: Vec<String> = CheckKind::value_variants()
+                        .iter()
+                        .filter_map(|kind| kind.to_possible_value())
+                        .map(|kind| kind.get_name().to_string())
+                        .collect();
+                    format!(
+                        "unknown check '{}', expected one of {}",
+                        value,
+                        names.join(", ")
+                    )
+                })?;
+                target.check = Some(check);
+            }
+            "tags" => {
+                target.tags = value
+                    .split(',')
+                    .filter(|t| !t.is_empty())
+                    .map(str::to_string)
+                    .collect();
+            }
+            "description" => target.description = Some(value.to_string()),
+            other => {
+                return Err(format!(
+                "unknown option '{}', expected one of timeout, retries, check, tags, description",
+                other
+            ))
+            }
+        }
+    }
+    Ok(target)
+}
+
+/// Split on whitespace, keeping double-quoted parts together without the quotes.
+fn split_words(line: &str) -> Result<Vec<String>, String> {
+    let mut words = Vec::new();
+    let mut word = String::new();
+    let mut quoted = false;
+    for c in line.chars() {
+        match c {
+            '"' => quoted = !quoted,
+            c if c.is_whitespace() && !quoted => {
+                if !word.is_empty() {
+                    words.push(std::mem::take(&mut word));
+                }
+            }
+            c => word.push(c),
+        }
+    }
+    if quoted {
+        return Err("unterminated quote".to_string());
+    }
+    if !word.is_empty() {
+        words.push(word);
+    }
+    Ok(words)
+}
+
+fn duration<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<Duration>, D::Error> {
+    let s = String::deserialize(deserializer)?;
+    duration::parse(&s)
+        .map(Some)
+        .map_err(serde::de::Error::custom)
+}
+
+fn addr<'de, D: Deserializer<'de>>(deserializer: D) -> Result<String, D::Error> {
+    let addr = String::deserialize(deserializer)?;
+    let addr = addr.trim().to_string();
+    crate::check_addr(&addr).map_err(serde::de::Error::custom)?;
+    Ok(addr)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// An empty directory of its own under the system temp dir.
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("tcp-probe-{}-{}", std::process::id(), name));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn split_words_keeps_quoted_parts_together() {
+        assert_eq!(
+            split_words(r#"db:5432  description="primary db"  tags=a,b"#),
+            Ok(vec![
+                "db:5432".to_string(),
+                "description=primary db".to_string(),
+                "tags=a,b".to_string(),
+            ])
+        );
+        assert_eq!(split_words("   "), Ok(Vec::new()));
+        assert!(split_words(r#"db:5432 description="primary"#).is_err());
+    }
+
+    #[test]
+    fn parse_line_reads_every_option() {
+        let target = parse_line(
+            r#"db:5432 timeout=2s retries=3 check=postgres tags=db,,prod description="primary db""#,
+        )
+        .unwrap();
+        assert_eq!(target.addr, "db:5432");
+        assert_eq!(target.timeout, Some(Duration::from_secs(2)));
+        assert_eq!(target.retries, Some(3));
+        assert!(matches!(target.check, Some(CheckKind::Postgres)));
+        assert_eq!(target.tags, ["db", "prod"]);
+        assert_eq!(target.description.as_deref(), Some("primary db"));
+    }
+
+    #[test]
+    fn parse_line_rejects_unknown_and_malformed_options() {
+        assert_eq!(
+            parse_line("db:5432 colour=red").unwrap_err(),
+            "unknown option 'colour', expected one of timeout, retries, check, tags, description"
+        );
+        assert_eq!(
+            parse_line("db:5432 timeout").unwrap_err(),
+            "expected key=value, got 'timeout'"
+        );
+        for line in [
+            "db:5432 retries=-1",
+            "db:5432 timeout=soon",
+            "db",
+            "db:http",
+        ] {
+            assert!(parse_line(line).is_err(), "{:?} should be rejected", line);
+        }
+    }
+
+    #[test]
+    fn parse_line_validates_check_names() {
+        assert!(matches!(
+            parse_line("db:5432 check=MySQL").unwrap().check,
+            Some(CheckKind::Mysql)
+        ));
+        let error = parse_line("db:5432 check=mongo").unwrap_err();
+        assert!(
+            error.starts_with("unknown check 'mongo', expected one of tcp, tls, redis"),
+            "{}",
+            error
+        );
+    }
+
+    #[test]
+    fn read_lines_follows_includes_and_skips_comments() {
+        let dir = temp_dir("include");
+        std::fs::write(dir.join("common.txt"), "# shared\ncache:6379 check=redis\n").unwrap();
+        std::fs::write(dir.join("main.txt"), "include common.txt\n\ndb:5432\n").unwrap();
+
+        let targets = read_targets_file(dir.join("main.txt").to_str().unwrap()).unwrap();
+        let addrs: Vec<_> = targets.iter().map(|t| t.addr.as_str()).collect();
+        assert_eq!(addrs, ["cache:6379", "db:5432"]);
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn read_lines_reports_include_cycles() {
+        let dir = temp_dir("cycle");
+        std::fs::write(dir.join("a.txt"), "db:5432\ninclude b.txt\n").unwrap();
+        std::fs::write(dir.join("b.txt"), "include a.txt\n").unwrap();
+
+        let error = read_targets_file(dir.join("a.txt").to_str().unwrap()).unwrap_err();
+        assert!(error.ends_with("a.txt includes itself"), "{}", error);
+        assert!(error.contains("b.txt:1: "), "{}", error);
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn read_lines_reports_the_bad_line() {
+        let dir = temp_dir("bad-line");
+        std::fs::write(
+            dir.join("targets.txt"),
+            "db:5432\n\ncache:6379 check=mongo\n",
+        )
+        .unwrap();
+
+        let path = dir.join("targets.txt");
+        let error = read_targets_file(path.to_str().unwrap()).unwrap_err();
+        assert!(
+            error.starts_with(&format!("{}:3: unknown check", path.display())),
+            "{}",
+            error
+        );
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,396 @@
+//! Minimal DNS client for resolving through a chosen server instead of the
+//! system resolver, and for the SRV lookups the system resolver cannot do.
+
+use serde::Serialize;
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::ops::Range;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpStream, UdpSocket};
+
+const TYPE_A: u16 = 1;
+const TYPE_AAAA: u16 = 28;
+const TYPE_SRV: u16 = 33;
+const CLASS_IN: u16 = 1;
+/// Largest UDP response accepted without EDNS.
+const MAX_UDP: usize = 512;
+/// Compression pointers followed before a name is considered looping.
+const MAX_POINTERS: usize = 16;
+
+/// One record of an SRV answer (RFC 2782).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SrvRecord {
+    pub priority: u16,
+    pub weight: u16,
+    pub port: u16,
+    /// Host name without the trailing dot; `"."` means the service is not offered.
+    pub target: String,
+}
+
+/// Ask `server` for the A and AAAA records of `name`, returning IPv4 addresses first.
+///
+/// The server is expected to recurse; CNAME chains are followed only as far as
+/// the server includes the final records in its answer.
+pub(crate) async fn lookup(server: SocketAddr, name: &str) -> Result<Vec<IpAddr>, String> {
+    let (v4, v6) = tokio::join!(
+        lookup_addrs(server, name, TYPE_A),
+        lookup_addrs(server, name, TYPE_AAAA)
+    );
+    match (v4, v6) {
+        (Ok(mut v4), Ok(v6)) => {
+            v4.extend(v6);
+            Ok(v4)
+        }
+        // One family failing only matters when the other found nothing.
+        (Ok(addrs), Err(e)) | (Err(e), Ok(addrs)) if addrs.is_empty() => Err(e),
+        (Ok(addrs), Err(_)) | (Err(_), Ok(addrs)) => Ok(addrs),
+        (Err(e), Err(_)) => Err(e),
+    }
+}
+
+/// Ask `server` for the SRV records of `name`, sorted by priority and then
+/// by descending weight.
+pub(crate) async fn lookup_srv(server: SocketAddr, name: &str) -> Result<Vec<SrvRecord>, String> {
+    let packet = query(server, name, TYPE_SRV).await?;
+    parse_srv(&packet, name)
+}
+
+/// Decode the SRV answers in `packet`, sorted as [`lookup_srv`] returns them.
+fn parse_srv(packet: &[u8], name: &str) -> Result<Vec<SrvRecord>, String> {
+    let mut records = Vec::new();
+    for rdata in answers(packet, name, TYPE_SRV)? {
+        let fields = packet
+            .get(rdata.start..rdata.start + 6)
+            .filter(|_| rdata.len() > 6)
+            .ok_or_else(|| "malformed SRV record".to_string())?;
+        let target =
+            read_name(packet, rdata.start + 6).ok_or_else(|| "malformed SRV target".to_string())?;
+        records.push(SrvRecord {
+            priority: u16::from_be_bytes([fields[0], fields[1]]),
+            weight: u16::from_be_bytes([fields[2], fields[3]]),
+            port: u16::from_be_bytes([fields[4], fields[5]]),
+            target,
+        });
+    }
+    records.sort_by_key(|r| (r.priority, std::cmp::Reverse(r.weight)));
+    Ok(records)
+}
+
+/// First `nameserver` in `/etc/resolv.conf`, for lookups the system resolver
+/// cannot do itself.
+pub(crate) fn system_server() -> Result<SocketAddr, String> {
+    let conf = std::fs::read_to_string("/etc/resolv.conf")
+        .map_err(|e| format!("cannot read /etc/resolv.conf: {}", e))?;
+    conf.lines()
+        .filter_map(|line| line.trim().strip_prefix("nameserver"))
+        .find_map(|ip| ip.trim().parse::<IpAddr>().ok())
+        .map(|ip| SocketAddr::new(ip, 53))
+        .ok_or_else(|| "no nameserver in /etc/resolv.conf".to_string())
+}
+
+async fn lookup_addrs(server: SocketAddr, name: &str, qtype: u16) -> Result<Vec<IpAddr>, String> {
+    let packet = query(server, name, qtype).await?;
+    answers(&packet, name, qtype)?
+        .into_iter()
+        .map(|rdata| match &packet[rdata] {
+            &[a, b, c, d] => Ok(IpAddr::from([a, b, c, d])),
+            data if data.len() == 16 => Ok(IpAddr::from(<[u8; 16]>::try_from(data).unwrap())),
+            _ => Err("malformed address record".to_string()),
+        })
+        .collect()
+}
+
+/// Send one query over UDP, retrying over TCP when the answer is truncated.
+async fn query(server: SocketAddr, name: &str, qtype: u16) -> Result<Vec<u8>, String> {
+    let id = RandomState::new().build_hasher().finish() as u16;
+    let request = encode_query(id, name, qtype)?;
+
+    let local: SocketAddr = if server.is_ipv4() {
+        (Ipv4Addr::UNSPECIFIED, 0).into()
+    } else {
+        (Ipv6Addr::UNSPECIFIED, 0).into()
+    };
+    let socket = UdpSocket::bind(local).await.map_err(|e| e.to_string())?;
+    socket.connect(server).await.map_err(|e| e.to_string())?;
+    socket.send(&request).await.map_err(|e| e.to_string())?;
+
+    let mut buf = [0u8; MAX_UDP];
+    let response = loop {
+        let n = socket.recv(&mut buf).await.map_err(|e| e.to_string())?;
+        // Stray datagrams (late replies to an earlier query) are ignored.
+        if n >= 4 && u16::from_be_bytes([buf[0], buf[1]]) == id {
+            break buf[..n].to_vec();
+        }
+    };
+    if response[2] & 0x02 == 0 {
+        return Ok(response);
+    }
+
+    let mut stream = TcpStream::connect(server)
+        .await
+        .map_err(|e| format!("TCP fallback: {}", e))?;
+    let mut framed = (request.len() as u16).to_be_bytes().to_vec();
+    framed.extend_from_slice(&request);
+    stream.write_all(&framed).await.map_err(|e| e.to_string())?;
+    let len = stream.read_u16().await.map_err(|e| e.to_string())?;
+    let mut response = vec![0u8; len as usize];
+    stream
+        .read_exact(&mut response)
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok(response)
+}
+
+fn encode_query(id: u16, name: &str, qtype: u16) -> Result<Vec<u8>, String> {
+    let mut packet = Vec::with_capacity(name.len() + 18);
+    packet.extend_from_slice(&id.to_be_bytes());
+    // Standard query, recursion desired; one question.
+    packet.extend_from_slice(&[0x01, 0x00, 0, 1, 0, 0, 0, 0, 0, 0]);
+    for label in name.trim_end_matches('.').split('.') {
+        if label.is_empty() || label.len() > 63 {
+            return Err(format!("invalid host name '{}'", name));
+        }
+        packet.push(label.len() as u8);
+        packet.extend_from_slice(label.as_bytes());
+    }
+    packet.push(0);
+    packet.extend_from_slice(&qtype.to_be_bytes());
+    packet.extend_from_slice(&CLASS_IN.to_be_bytes());
+    Ok(packet)
+}
+
+/// Check the response code and return where the data of each `qtype` answer lies.
+fn answers(packet: &[u8], name: &str, qtype: u16) -> Result<Vec<Range<usize>>, String> {
+    let truncated = || "truncated DNS response".to_string();
+    let header = packet.get(..12).ok_or_else(truncated)?;
+    let flags = u16::from_be_bytes([header[2], header[3]]);
+    match flags & 0x000f {
+        0 => {}
+        3 => return Err(format!("{}: no such host", name)),
+        2 => return Err("server failure (SERVFAIL)".to_string()),
+        5 => return Err("query refused (REFUSED)".to_string()),
+        rcode => return Err(format!("DNS error code {}", rcode)),
+    }
+    let questions = u16::from_be_bytes([header[4], header[5]]);
+    let answers = u16::from_be_bytes([header[6], header[7]]);
+
+    let mut pos = 12;
+    for _ in 0..questions {
+        pos = skip_name(packet, pos).ok_or_else(truncated)? + 4;
+    }
+
+    let mut found = Vec::new();
+    for _ in 0..answers {
+        pos = skip_name(packet, pos).ok_or_else(truncated)?;
+        let record = packet.get(pos..pos + 10).ok_or_else(truncated)?;
+        let rtype = u16::from_be_bytes([record[0], record[1]]);
+        let len = u16::from_be_bytes([record[8], record[9]]) as usize;
+        let data = pos + 10..pos + 10 + len;
+        if data.end > packet.len() {
+            return Err(truncated());
+        }
+        pos = data.end;
+        if rtype == qtype {
+            found.push(data);
+        }
+    }
+    Ok(found)
+}
+
+/// Position just past the (possibly compressed) name starting at `pos`.
+fn skip_name(packet: &[u8], mut pos: usize) -> Option<usize> {
+    loop {
+        let len = *packet.get(pos)?;
+        match len {
+            0 => return Some(pos + 1),
+            // A compression pointer ends the name.
+            len if len & 0xc0 == 0xc0 => return Some(pos + 2),
+            len => pos += 1 + len as usize,
+        }
+    }
+}
+
+/// Decode the name at `pos`, following compression pointers.
+fn read_name(packet: &[u8], mut pos: usize) -> Option<String> {
+    let mut labels = Vec::new();
+    let mut pointers = 0;
+    loop {
+        let len = *packet.get(pos)? as usize;
+        match len {
+            0 => break,
+            len if len & 0xc0 == 0xc0 => {
+                pointers += 1;
+                if pointers > MAX_POINTERS {
+                    return None;
+                }
+                pos = (len & 0x3f) << 8 | *packet.get(pos + 1)? as usize;
+            }
+            len => {
+                let label = packet.get(pos + 1..pos + 1 + len)?;
+                labels.push(String::from_utf8_lossy(label).into_owned());
+                pos += 1 + len;
+            }
+        }
+    }
+    if labels.is_empty() {
+        return Some(".".to_string());
+    }
+    Some(labels.join("."))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A response to a `qtype` query for `name`, with `records` (type and
+    /// data) as answers pointing back at the question name.
+    fn response(name: &str, qtype: u16, rcode: u8, records: &[(u16, Vec<u8>)]) -> Vec<u8> {
+        let mut packet = encode_query(7, name, qtype).unwrap();
+        packet[2] = 0x81;
+        packet[3] = 0x80 | rcode;
+        packet[7] = records.len() as u8;
+        for (rtype, data) in records {
+            packet.extend_from_slice(&[0xc0, 12]);
+            packet.extend_from_slice(&rtype.to_be_bytes());
+            packet.extend_from_slice(&CLASS_IN.to_be_bytes());
+            packet.extend_from_slice(&60u32.to_be_bytes());
+            packet.extend_from_slice(&(data.len() as u16).to_be_bytes());
+            packet.extend_from_slice(data);
+        }
+        packet
+    }
+
+    fn srv(priority: u16, weight: u16, port: u16, target: &[u8]) -> Vec<u8> {
+        let mut data = Vec::new();
+        for field in [priority, weight, port] {
+            data.extend_from_slice(&field.to_be_bytes());
+        }
+        data.extend_from_slice(target);
+        data
+    }
+
+    #[test]
+    fn encode_query_writes_labels() {
+        let packet = encode_query(0x1234, "db.internal.", TYPE_A).unwrap();
+        assert_eq!(&packet[..2], &[0x12, 0x34]);
+        assert_eq!(&packet[12..], b"\x02db\x08internal\x00\x00\x01\x00\x01");
+    }
+
+    #[test]
+    fn encode_query_rejects_empty_and_long_labels() {
+        let long = format!("{}.example", "a".repeat(64));
+        for name in ["", ".", "a..b", ".example", long.as_str()] {
+            assert!(
+                encode_query(1, name, TYPE_A).is_err(),
+                "{:?} should be rejected",
+                name
+            );
+        }
+        let longest = format!("{}.example", "a".repeat(63));
+        assert!(encode_query(1, &longest, TYPE_A).is_ok());
+    }
+
+    #[test]
+    fn answers_returns_matching_record_data() {
+        let packet = response(
+            "db.internal",
+            TYPE_A,
+            0,
+            &[(5, vec![0xc0, 12]), (TYPE_A, vec![10, 0, 0, 1])],
+        );
+        let found = answers(&packet, "db.internal", TYPE_A).unwrap();
+        assert_eq!(found.len(), 1);
+        assert_eq!(&packet[found[0].clone()], &[10, 0, 0, 1]);
+    }
+
+    #[test]
+    fn answers_reports_error_codes() {
+        let packet = response("nope.internal", TYPE_A, 3, &[]);
+        assert_eq!(
+            answers(&packet, "nope.internal", TYPE_A),
+            Err("nope.internal: no such host".to_string())
+        );
+        let packet = response("db.internal", TYPE_A, 2, &[]);
+        assert_eq!(
+            answers(&packet, "db.internal", TYPE_A),
+            Err("server failure (SERVFAIL)".to_string())
+        );
+    }
+
+    #[test]
+    fn answers_rejects_truncated_records() {
+        let packet = response("db.internal", TYPE_A, 0, &[(TYPE_A, vec![10, 0, 0, 1])]);
+        // Cut inside the record data, inside the record header, and inside the header.
+        for len in [packet.len() - 1, packet.len() - 8, 11] {
+            assert_eq!(
+                answers(&packet[..len], "db.internal", TYPE_A),
+                Err("truncated DNS response".to_string()),
+                "cut at {}",
+                len
+            );
+        }
+    }
+
+    #[test]
+    fn read_name_follows_pointers() {
+        let packet = response("db.internal", TYPE_A, 0, &[]);
+        assert_eq!(read_name(&packet, 12).as_deref(), Some("db.internal"));
+        let mut packet = packet;
+        let pos = packet.len();
+        packet.extend_from_slice(b"\x03www\xc0\x0f");
+        assert_eq!(read_name(&packet, pos).as_deref(), Some("www.internal"));
+    }
+
+    #[test]
+    fn read_name_stops_pointer_loops() {
+        // A pointer to itself, and two pointers to each other.
+        assert_eq!(read_name(&[0xc0, 0], 0), None);
+        assert_eq!(read_name(&[0xc0, 2, 0xc0, 0], 0), None);
+        // A chain as long as allowed still resolves; one more pointer does not.
+        let mut packet = vec![0];
+        let mut last = 0;
+        for _ in 0..=MAX_POINTERS {
+            let pos = packet.len();
+            packet.extend_from_slice(&[0xc0, last as u8]);
+            last = pos;
+        }
+        assert_eq!(read_name(&packet, last - 2).as_deref(), Some("."));
+        assert_eq!(read_name(&packet, last), None);
+    }
+
+    #[test]
+    fn parse_srv_sorts_by_priority_then_weight() {
+        let name = "_pg._tcp.db.internal";
+        let packet = response(
+            name,
+            TYPE_SRV,
+            0,
+            &[
+                (TYPE_SRV, srv(20, 0, 5432, b"\x01c\x00")),
+                (TYPE_SRV, srv(10, 20, 5432, b"\x01b\x00")),
+                (TYPE_SRV, srv(10, 60, 5433, b"\x01a\x00")),
+            ],
+        );
+        let records = parse_srv(&packet, name).unwrap();
+        let order: Vec<_> = records
+            .iter()
+            .map(|r| (r.target.as_str(), r.port))
+            .collect();
+        assert_eq!(order, [("a", 5433), ("b", 5432), ("c", 5432)]);
+    }
+
+    #[test]
+    fn parse_srv_rejects_short_rdata() {
+        let name = "_pg._tcp.db.internal";
+        for data in [vec![], vec![0; 6], srv(10, 0, 5432, b"")] {
+            let packet = response(name, TYPE_SRV, 0, &[(TYPE_SRV, data.clone())]);
+            assert_eq!(
+                parse_srv(&packet, name),
+                Err("malformed SRV record".to_string()),
+                "{:?}",
+                data
+            );
+        }
+    }
+}
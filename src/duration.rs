@@ -0,0 +1,141 @@
+//! Durations as written on the command line and in config files.
+
+use std::time::Duration;
+
+/// Units from largest to smallest, with their length in seconds.
+const UNITS: [(&str, f64); 7] = [
+    ("h", 3600.0),
+    ("m", 60.0),
+    ("s", 1.0),
+    ("ms", 1e-3),
+    ("us", 1e-6),
+    ("µs", 1e-6),
+    ("ns", 1e-9),
+];
+
+/// Parse a duration such as `5s`, `250ms`, `1.5s`, `1m30s` or `500us`.
+///
+/// Units are `h`, `m`, `s`, `ms`, `us` (or `µs`) and `ns`, combined from the
+/// largest to the smallest with each used once. A bare number is taken as
+/// seconds. Anything else is an error rather than a silent default.
+pub fn parse(s: &str) -> Result<Duration, String> {
+    let invalid = |why: &str| format!("invalid duration '{}': {}", s, why);
+    let input = s.trim();
+    if input.is_empty() {
+        return Err("empty duration".to_string());
+    }
+    if input.contains(char::is_whitespace) {
+        return Err(invalid("remove the spaces, e.g. 500ms"));
+    }
+    if input.chars().all(|c| c.is_ascii_digit() || c == '.') {
+        let secs = input.parse().map_err(|_| invalid("bad number"))?;
+        return Duration::try_from_secs_f64(secs).map_err(|_| invalid("too large"));
+    }
+
+    let mut rest = input;
+    let mut secs = 0.0;
+    let mut smallest = f64::INFINITY;
+    while !rest.is_empty() {
+        let split = rest
+            .find(|c: char| !c.is_ascii_digit() && c != '.')
+            .unwrap_or(rest.len());
+        let (number, tail) = rest.split_at(split);
+        let split = tail
+            .find(|c: char| c.is_ascii_digit() || c == '.')
+            .unwrap_or(tail.len());
+        let (unit, tail) = tail.split_at(split);
+
+        if number.is_empty() {
+            return Err(invalid(&format!("expected a number before '{}'", unit)));
+        }
+        let value: f64 = number
+            .parse()
+            .map_err(|_| invalid(&format!("bad number '{}'", number)))?;
+        if unit.is_empty() {
+            return Err(invalid(&format!("missing unit after '{}'", number)));
+        }
+        let scale = UNITS
+            .iter()
+            .find(|(name, _)| *name == unit)
+            .map(|&(_, scale)| scale)
+            .ok_or_else(|| {
+                invalid(&format!(
+                    "unknown unit '{}', expected h, m, s, ms, us or ns",
+                    unit
+                ))
+            })?;
+        if scale >= smallest {
+            return Err(invalid("units must go from largest to smallest, each once"));
+        }
+        smallest = scale;
+        secs += value * scale;
+        rest = tail;
+    }
+    Duration::try_from_secs_f64(secs).map_err(|_| invalid("too large"))
+}
+
+/// Render a duration the way [`parse`] reads it, to the second (or to the
+/// millisecond below one second): `250ms`, `45s`, `1m30s`, `2h5m`.
+pub fn format(duration: Duration) -> String {
+    if duration < Duration::from_secs(1) {
+        return format!("{}ms", duration.as_millis());
+    }
+    let secs = duration.as_secs();
+    let (h, m, s) = (secs / 3600, secs / 60 % 60, secs % 60);
+    let mut out = String::new();
+    if h > 0 {
+        out.push_str(&format!("{}h", h));
+    }
+    if m > 0 {
+        out.push_str(&format!("{}m", m));
+    }
+    if s > 0 || out.is_empty() {
+        out.push_str(&format!("{}s", s));
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_single_units() {
+        assert_eq!(parse("500ms"), Ok(Duration::from_millis(500)));
+        assert_eq!(parse("1.5s"), Ok(Duration::from_millis(1500)));
+        assert_eq!(parse("250us"), Ok(Duration::from_micros(250)));
+        assert_eq!(parse("2h"), Ok(Duration::from_secs(7200)));
+    }
+
+    #[test]
+    fn parses_compound_durations() {
+        assert_eq!(parse("1m30s"), Ok(Duration::from_secs(90)));
+        assert_eq!(parse("1s500ms"), Ok(Duration::from_millis(1500)));
+    }
+
+    #[test]
+    fn bare_number_is_seconds() {
+        assert_eq!(parse("5"), Ok(Duration::from_secs(5)));
+    }
+
+    #[test]
+    fn rejects_invalid_input() {
+        for input in ["3sec", "500 ms", "1s1m", "1s1s", "", "ms", "1..5s", "5x"] {
+            assert!(parse(input).is_err(), "{:?} should be rejected", input);
+        }
+    }
+
+    #[test]
+    fn format_round_trips_through_parse() {
+        for d in [
+            Duration::from_millis(250),
+            Duration::from_secs(45),
+            Duration::from_secs(90),
+            Duration::from_secs(7500),
+            Duration::from_secs(3600),
+        ] {
+            assert_eq!(parse(&format(d)), Ok(d), "{}", format(d));
+        }
+        assert_eq!(format(Duration::from_secs(90)), "1m30s");
+    }
+}
@@ -0,0 +1,223 @@
+//! Host patterns that stand for many hosts: CIDR blocks and brace ranges.
+//!
+//! Expansion is lazy, so callers can cap a pattern like `10.0.0.0/8` without
+//! materializing sixteen million addresses first.
+
+use std::iter;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+/// A lazily expanded list of host names or addresses.
+pub type Hosts = Box<dyn Iterator<Item = String> + Send>;
+
+/// Whether `host` is a pattern [`hosts`] would expand.
+pub fn is_pattern(host: &str) -> bool {
+    host.contains(['{', '/'])
+}
+
+/// Expand a host pattern.
+///
+/// * `10.0.3.0/28` yields the usable addresses of the block. IPv4 blocks
+///   larger than a /31 skip the network and broadcast addresses. IPv6 blocks
+///   are written in brackets, `[2001:db8::/120]`, and yield bracketed addresses.
+/// * `web-{01..12}.internal` yields `web-01.internal` to `web-12.internal`. A
+///   leading zero pads every number to the same width, ranges may count down,
+///   `{a,b,c}` lists alternatives, and several braces multiply.
+///
+/// Anything else yields the host unchanged.
+pub fn hosts(pattern: &str) -> Result<Hosts, String> {
+    if pattern.contains('/') {
+        cidr(pattern)
+    } else {
+        braces(pattern)
+    }
+}
+
+fn cidr(pattern: &str) -> Result<Hosts, String> {
+    let invalid = |msg: &str| format!("invalid CIDR block '{}': {}", pattern, msg);
+    let bracketed = pattern.starts_with('[') && pattern.ends_with(']');
+    let block = pattern.trim_start_matches('[').trim_end_matches(']');
+    let (addr, prefix) = block
+        .split_once('/')
+        .ok_or_else(|| invalid("missing prefix"))?;
+    let addr: IpAddr = addr.parse().map_err(|_| invalid("bad address"))?;
+    let prefix: u32 = prefix.parse().map_err(|_| invalid("bad prefix length"))?;
+
+    match addr {
+        IpAddr::V4(addr) => {
+            if prefix > 32 {
+                return Err(invalid("prefix longer than 32"));
+            }
+            let mask = u32::MAX.checked_shl(32 - prefix).unwrap_or(0);
+            let first = u32::from(addr) & mask;
+            let last = first | !mask;
+            let (first, last) = if prefix < 31 {
+                (first + 1, last - 1)
+            } else {
+                (first, last)
+            };
+            Ok(Box::new(
+                (first..=last).map(|n| Ipv4Addr::from(n).to_string()),
+            ))
+        }
+        IpAddr::V6(addr) => {
+            if !bracketed {
+                return Err(invalid(
+                    "IPv6 blocks must be in brackets, e.g. [2001:db8::/120]",
+                ));
+            }
+            if prefix > 128 {
+                return Err(invalid("prefix longer than 128"));
+            }
+            let mask = u128::MAX.checked_shl(128 - prefix).unwrap_or(0);
+            let first = u128::from(addr) & mask;
+            let last = first | !mask;
+            Ok(Box::new(
+                (first..=last).map(|n| format!("[{}]", Ipv6Addr::from(n))),
+            ))
+        }
+    }
+}
+
+fn braces(pattern: &str) -> Result<Hosts, String> {
+    let Some(open) = pattern.find('{') else {
+        if pattern.contains('}') {
+            return Err(format!("unmatched '}}' in '{}'", pattern));
+        }
+        return Ok(Box::new(iter::once(pattern.to_string())));
+    };
+    let close = pattern[open..]
+        .find('}')
+        .map(|i| open + i)
+        .ok_or_else(|| format!("unmatched '{{' in '{}'", pattern))?;
+
+    let prefix = pattern[..open].to_string();
+    let body = &pattern[open + 1..close];
+    let rest = pattern[close + 1..].to_string();
+    if prefix.contains('}') {
+        return Err(format!("unmatched '}}' in '{}'", pattern));
+    }
+    let values = brace_values(body).map_err(|e| format!("{} in '{}'", e, pattern))?;
+    // Fail now rather than halfway through the expansion.
+    drop(braces(&rest)?);
+
+    Ok(Box::new(values.flat_map(move |value| {
+        let head = format!("{}{}", prefix, value);
+        braces(&rest)
+            .expect("checked above")
+            .map(move |tail| format!("{}{}", head, tail))
+    })))
+}
+
+/// `01..12`, `12..1` or `a,b,c`.
+fn brace_values(body: &str) -> Result<Hosts, String> {
+    if let Some((start, end)) = body.split_once("..") {
+        let number = |s: &str| {
+            s.parse::<u64>()
+                .map_err(|_| format!("invalid range '{{{}}}'", body))
+        };
+        let (from, to) = (number(start)?, number(end)?);
+        let padded = |s: &str| s.len() > 1 && s.starts_with('0');
+        let width = if padded(start) || padded(end) {
+            start.len().max(end.len())
+        } else {
+            0
+        };
+        let format = move |n: u64| format!("{:0width$}", n, width = width);
+        return Ok(if from <= to {
+            Box::new((from..=to).map(format))
+        } else {
+            Box::new((to..=from).rev().map(format))
+        });
+    }
+    if body.contains(',') {
+        let values: Vec<String> = body.split(',').map(str::to_string).collect();
+        return Ok(Box::new(values.into_iter()));
+    }
+    Err(format!("invalid brace expression '{{{}}}'", body))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn expand(pattern: &str) -> Vec<String> {
+        hosts(pattern).unwrap().collect()
+    }
+
+    #[test]
+    fn ipv4_block_skips_network_and_broadcast() {
+        assert_eq!(
+            expand("10.0.3.0/29"),
+            ["10.0.3.1", "10.0.3.2", "10.0.3.3", "10.0.3.4", "10.0.3.5", "10.0.3.6"]
+        );
+        assert_eq!(expand("10.0.3.9/30"), ["10.0.3.9", "10.0.3.10"]);
+    }
+
+    #[test]
+    fn ipv4_point_to_point_and_host_blocks_keep_every_address() {
+        assert_eq!(expand("10.0.3.0/31"), ["10.0.3.0", "10.0.3.1"]);
+        assert_eq!(expand("10.0.3.7/32"), ["10.0.3.7"]);
+    }
+
+    #[test]
+    fn ipv6_blocks_need_brackets() {
+        assert_eq!(
+            expand("[2001:db8::/127]"),
+            ["[2001:db8::]", "[2001:db8::1]"]
+        );
+        assert!(hosts("2001:db8::/127").is_err());
+    }
+
+    #[test]
+    fn rejects_bad_cidr_blocks() {
+        for pattern in ["10.0.0.0/33", "10.0.0/24", "10.0.0.0/x", "[2001:db8::/129]"] {
+            assert!(hosts(pattern).is_err(), "{:?} should be rejected", pattern);
+        }
+    }
+
+    #[test]
+    fn numeric_ranges_pad_to_the_widest_bound() {
+        assert_eq!(expand("web-{08..10}"), ["web-08", "web-09", "web-10"]);
+        assert_eq!(expand("web-{1..3}"), ["web-1", "web-2", "web-3"]);
+        assert_eq!(expand("n{001..2}"), ["n001", "n002"]);
+    }
+
+    #[test]
+    fn ranges_may_count_down() {
+        assert_eq!(expand("web-{3..1}"), ["web-3", "web-2", "web-1"]);
+    }
+
+    #[test]
+    fn braces_multiply() {
+        assert_eq!(
+            expand("{a,b}-{1..2}.internal"),
+            [
+                "a-1.internal",
+                "a-2.internal",
+                "b-1.internal",
+                "b-2.internal"
+            ]
+        );
+    }
+
+    #[test]
+    fn plain_hosts_pass_through() {
+        assert_eq!(expand("db.internal"), ["db.internal"]);
+        assert!(!is_pattern("db.internal"));
+        assert!(is_pattern("web-{1..2}"));
+    }
+
+    #[test]
+    fn rejects_unmatched_and_invalid_braces() {
+        for pattern in [
+            "web-{1..2",
+            "web-1..2}",
+            "a}{b,c}",
+            "{a,b}-{1..",
+            "web-{x..y}",
+            "web-{1}",
+        ] {
+            assert!(hosts(pattern).is_err(), "{:?} should be rejected", pattern);
+        }
+    }
+}
@@ -0,0 +1,147 @@
+use std::fmt;
+use std::str::FromStr;
+
+use crate::result::{ProbeResult, Status, Timings};
+
+/// Members named in a group's error or warning before the rest are only counted.
+const MAX_LISTED: usize = 5;
+
+/// How many members of a group must be healthy for the group to pass.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MinHealthy {
+    /// Every member.
+    All,
+    /// At least this many members; fails when there are fewer.
+    Count(usize),
+    /// At least this share of the members, rounded up.
+    Percent(u8),
+}
+
+impl MinHealthy {
+    /// Healthy members needed out of `total`.
+    pub fn required(self, total: usize) -> usize {
+        match self {
+            MinHealthy::All => total,
+            // Fewer members than asked for cannot meet the rule.
+            MinHealthy::Count(n) => n,
+            MinHealthy::Percent(p) => (total * p as usize).div_ceil(100),
+        }
+    }
+}
+
+impl FromStr for MinHealthy {
+    type Err = String;
+
+    /// `all`, a count such as `2`, or a percentage such as `50%`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || {
+            format!(
+                "invalid rule '{}': expected all, a count or a percentage",
+                s
+            )
+        };
+        if s.eq_ignore_ascii_case("all") {
+            return Ok(MinHealthy::All);
+        }
+        match s.strip_suffix('%') {
+            Some(p) => match p.parse::<u8>() {
+                Ok(p) if p <= 100 => Ok(MinHealthy::Percent(p)),
+                _ => Err(invalid()),
+            },
+            None => s.parse().map(MinHealthy::Count).map_err(|_| invalid()),
+        }
+    }
+}
+
+impl fmt::Display for MinHealthy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MinHealthy::All => write!(f, "all"),
+            MinHealthy::Count(n) => write!(f, "{}", n),
+            MinHealthy::Percent(p) => write!(f, "{}%", p),
+        }
+    }
+}
+
+impl ProbeResult {
+    /// Combine the results of related targets (the hosts behind an SRV name,
+    /// the ports of one host, ...) into a single result named `name`.
+    ///
+    /// The group passes when `rule` is met; it warns when some members are
+    /// down but enough are healthy.
+    pub fn group(name: impl Into<String>, members: Vec<ProbeResult>, rule: MinHealthy) -> Self {
+        let healthy = members.iter().filter(|m| m.is_healthy()).count();
+        let required = rule.required(members.len());
+        let down: Vec<&str> = members
+            .iter()
+            .filter(|m| !m.is_healthy())
+            .map(|m| m.host.as_str())
+            .collect();
+        let listed = match down.len() {
+            n if n > MAX_LISTED => format!(
+                "{} and {} more",
+                down[..MAX_LISTED].join(", "),
+                n - MAX_LISTED
+            ),
+            _ => down.join(", "),
+        };
+
+        let (status, error, warning) = if members.is_empty() {
+            (Status::Fail, Some("no targets".to_string()), None)
+        } else if healthy < required {
+            let status = members
+                .iter()
+                .map(|m| m.status)
+                .find(|s| !s.is_healthy())
+                .unwrap_or(Status::Fail);
+            let mut error = format!("{}/{} healthy, need {}", healthy, members.len(), required);
+            if !down.is_empty() {
+                error.push_str(&format!("; down: {}", listed));
+            }
+            (status, Some(error), None)
+        } else if !down.is_empty() {
+            let warning = format!("{}/{} down: {}", down.len(), members.len(), listed);
+            (Status::Warn, None, Some(warning))
+        } else if members.iter().any(|m| m.status == Status::Warn) {
+            (
+                Status::Warn,
+                None,
+                Some("members have warnings".to_string()),
+            )
+        } else {
+            (Status::Ok, None, None)
+        };
+
+        let total_ms = members
+            .iter()
+            .map(|m| m.timings.total_ms)
+            .fold(0.0, f64::max);
+        ProbeResult {
+            host: name.into(),
+            description: None,
+            tags: Vec::new(),
+            status,
+            latency_ms: members
+                .iter()
+                .filter_map(|m| m.latency_ms)
+                .min_by(f64::total_cmp),
+            error,
+            warning,
+            retries_used: members.iter().map(|m| m.retries_used).max().unwrap_or(0),
+            timings: Timings {
+                total_ms,
+                ..Timings::default()
+            },
+            check: members.first().and_then(|m| m.check.clone()),
+            banner: None,
+            tls: None,
+            http: None,
+            addresses: Vec::new(),
+            happy_eyeballs: None,
+            members,
+            details: [("min_healthy".to_string(), rule.to_string().into())]
+                .into_iter()
+                .collect(),
+        }
+    }
+}
@@ -0,0 +1,152 @@
+//! Connection racing per RFC 8305 ("Happy Eyeballs v2").
+
+use std::net::SocketAddr;
+use std::time::Duration;
+use tokio::net::TcpStream;
+use tokio::task::JoinSet;
+use tokio::time::error::Elapsed;
+use tokio::time::{sleep, timeout, Instant};
+
+use crate::result::{RaceAttempt, RaceResult};
+
+/// RFC 8305 section 5 recommends 250ms between connection attempts.
+pub const CONNECTION_ATTEMPT_DELAY: Duration = Duration::from_millis(250);
+
+/// Order addresses for racing: IPv6 first, then alternating families (RFC 8305 section 4).
+pub(crate) fn interleave(addrs: &[SocketAddr]) -> Vec<SocketAddr> {
+    let (mut v6, mut v4): (Vec<SocketAddr>, Vec<SocketAddr>) =
+        addrs.iter().partition(|a| a.is_ipv6());
+    v6.reverse();
+    v4.reverse();
+
+    let mut ordered = Vec::with_capacity(addrs.len());
+    while !v6.is_empty() || !v4.is_empty() {
+        ordered.extend(v6.pop());
+        ordered.extend(v4.pop());
+    }
+    ordered
+}
+
+/// Start a connection attempt every `delay` (or as soon as the previous one
+/// fails) until one succeeds; the losers are cancelled.
+pub(crate) async fn race(
+    addrs: &[SocketAddr],
+    connect_timeout: Duration,
+    delay: Duration,
+) -> (Option<(SocketAddr, TcpStream)>, RaceResult) {
+    let ordered = interleave(addrs);
+    let start = Instant::now();
+    let mut attempts: Vec<RaceAttempt> = ordered
+        .iter()
+        .map(|addr| RaceAttempt {
+            addr: addr.to_string(),
+            family: if addr.is_ipv6() { "ipv6" } else { "ipv4" },
+            started_ms: None,
+            elapsed_ms: None,
+            outcome: "not_started",
+            error: None,
+        })
+        .collect();
+
+    let mut tasks = JoinSet::new();
+    let mut next = 0;
+    let mut winner = None;
+
+    loop {
+        if tasks.is_empty() && next < ordered.len() {
+            launch(
+                &mut tasks,
+                &mut attempts,
+                &ordered,
+                next,
+                start,
+                connect_timeout,
+            );
+            next += 1;
+        }
+        if tasks.is_empty() {
+            break;
+        }
+
+        let joined = tokio::select! {
+            joined = tasks.join_next() => joined,
+            _ = sleep(delay), if next < ordered.len() => {
+                launch(&mut tasks, &mut attempts, &ordered, next, start, connect_timeout);
+                next += 1;
+                continue;
+            }
+        };
+        let Some(Ok((index, elapsed, result))) = joined else {
+            continue;
+        };
+
+        let attempt = &mut attempts[index];
+        attempt.elapsed_ms = Some(ms(elapsed));
+        match result {
+            Ok(Ok(stream)) => {
+                attempt.outcome = "won";
+                winner = Some((ordered[index], stream));
+                break;
+            }
+            Ok(Err(e)) => {
+                attempt.outcome = "failed";
+                attempt.error = Some(format!("Connection refused: {}", e));
+            }
+            Err(_) => {
+                attempt.outcome = "failed";
+                attempt.error = Some(format!("timeout ({}ms)", connect_timeout.as_millis()));
+            }
+        }
+        // A failed attempt starts the next one right away instead of waiting out the delay.
+        if next < ordered.len() {
+            launch(
+                &mut tasks,
+                &mut attempts,
+                &ordered,
+                next,
+                start,
+                connect_timeout,
+            );
+            next += 1;
+        }
+    }
+
+    tasks.abort_all();
+    for attempt in attempts.iter_mut().filter(|a| a.outcome == "pending") {
+        attempt.outcome = "cancelled";
+        attempt.elapsed_ms = attempt.started_ms.map(|s| ms(start.elapsed()) - s);
+    }
+
+    let race = RaceResult {
+        winner: winner.as_ref().map(|(addr, _)| addr.to_string()),
+        family: winner
+            .as_ref()
+            .map(|(addr, _)| if addr.is_ipv6() { "ipv6" } else { "ipv4" }),
+        attempts,
+    };
+    (winner, race)
+}
+
+type Attempt = (usize, Duration, Result<std::io::Result<TcpStream>, Elapsed>);
+
+fn launch(
+    tasks: &mut JoinSet<Attempt>,
+    attempts: &mut [RaceAttempt],
+    ordered: &[SocketAddr],
+    index: usize,
+    start: Instant,
+    connect_timeout: Duration,
+) {
+    let addr = ordered[index];
+    attempts[index].started_ms = Some(ms(start.elapsed()));
+    attempts[index].outcome = "pending";
+    tasks.spawn(async move {
+        let begun = Instant::now();
+        let result = timeout(connect_timeout, TcpStream::connect(addr)).await;
+        (index, begun.elapsed(), result)
+    });
+}
+
+fn ms(d: Duration) -> f64 {
+    d.as_secs_f64() * 1000.0
+}
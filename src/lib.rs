@@ -0,0 +1,28 @@
+//! Fast TCP health probes.
+//!
+//! The `tcp-probe` binary is a thin CLI over this crate; services can use
+//! [`Prober`] directly to run the same checks at startup.
+
+mod check;
+pub mod checks;
+mod dns;
+pub mod duration;
+pub mod escape;
+pub mod expand;
+mod group;
+mod happy_eyeballs;
+mod probe;
+mod result;
+mod target;
+pub mod url;
+
+pub use check::{Check, CheckContext, CheckError, CheckOutcome};
+pub use dns::SrvRecord;
+pub use group::MinHealthy;
+pub use happy_eyeballs::CONNECTION_ATTEMPT_DELAY;
+pub use probe::{probe_host, AddressMode, Prober};
+pub use result::{
+    AddressResult, HttpInfo, ProbeResult, RaceAttempt, RaceResult, Status, Summary, Timings,
+    TlsInfo,
+};
+pub use target::Target;
@@ -1,135 +1,735 @@
-use clap::Parser;
-use colored::Colorize;
-use serde::Serialize;
-use std::fs;
-use std::net::ToSocketAddrs;
-use std::time::{Duration, Instant};
-use tokio::net::TcpStream;
-use tokio::time::timeout;
+use clap::{Parser, ValueEnum};
+use colored::{ColoredString, Colorize};
+use serde_json::{Map, Value};
+use std::net::{IpAddr, SocketAddr};
+use std::sync::Arc;
+use std::time::Duration;
+use tcp_probe::checks::{
+    Banner, Http, Mysql, Postgres, Redis, RedisRole, SendExpect, Smtp, Ssh, Tls,
+};
+use tcp_probe::duration;
+use tcp_probe::escape::unescape;
+use tcp_probe::expand;
+use tcp_probe::url::TargetUrl;
+use tcp_probe::{
+    AddressMode, Check, MinHealthy, ProbeResult, Prober, Status, Summary, Target, TlsInfo,
+};
+
+mod config;
+mod ping;
+mod stats;
+mod watch;
+
+use config::{Config, Defaults};
+
+#[derive(Debug, Clone, Copy, ValueEnum, serde::Deserialize)]
+#[serde(rename_all = "kebab-case")]
+enum CheckKind {
+    /// TCP connect only
+    Tcp,
+    /// TLS handshake and certificate details
+    Tls,
+    /// Redis PING, optionally with AUTH and a replication role check
+    Redis,
+    /// PostgreSQL SSLRequest, optionally followed by a StartupMessage
+    Postgres,
+    /// MySQL/MariaDB handshake greeting
+    Mysql,
+    /// SSH identification line, optionally checked against a pattern or minimum version
+    Ssh,
+    /// SMTP greeting and EHLO extensions, optionally with STARTTLS
+    Smtp,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum Addresses {
+    /// Probe only the first resolved address
+    First,
+    /// Probe every resolved address; any dead one fails the target
+    All,
+    /// Probe every resolved address; dead ones only warn while one is healthy
+    Any,
+    /// Race IPv6 and IPv4 connects with staggered starts (RFC 8305)
+    HappyEyeballs,
+}
 
 #[derive(Parser, Debug)]
 #[command(name = "tcp-probe", about = "Fast TCP health probe")]
 struct Args {
-    /// Target hosts (host:port)
+    /// Target hosts (host:port, host:80,443,8000-8010, 10.0.3.0/28:22,
+    /// web-{01..12}.internal:443, srv:_service._tcp.name, or URLs such as
+    /// https://api.example.com, postgres://db/app and redis://cache)
     targets: Vec<String>,
 
-    /// Timeout per connection attempt
-    #[arg(short, long, default_value = "5s")]
-    timeout: String,
+    /// Timeout per connection attempt (e.g. 5s, 250ms, 1m30s)
+    #[arg(short, long, default_value = "5s", value_parser = duration::parse)]
+    timeout: Duration,
+
+    /// Timeout for resolving each target's name (default: same as --timeout)
+    #[arg(long, value_parser = duration::parse)]
+    dns_timeout: Option<Duration>,
+
+    /// Query this DNS server (ip or ip:port) instead of the system resolver
+    #[arg(long, value_name = "IP:PORT")]
+    dns_server: Option<String>,
+
+    /// Refuse to run when targets expand (CIDR blocks, brace ranges, port lists,
+    /// SRV records) to more probes than this
+    #[arg(long, default_value_t = 1024)]
+    max_targets: usize,
+
+    /// How many members of a group must be healthy: all, a count or a percentage
+    /// (default: 1 for srv: targets, all for port lists)
+    #[arg(long, value_name = "RULE")]
+    min_healthy: Option<MinHealthy>,
+
+    /// Pin host:port to an address, keeping the name for SNI and Host (repeatable)
+    #[arg(long, value_name = "HOST:PORT:ADDR")]
+    resolve: Vec<String>,
 
     /// Number of retries on failure
     #[arg(short, long, default_value_t = 0)]
     retries: u32,
 
+    /// Pause before the first retry; each further retry waits one step longer
+    #[arg(long, default_value = "100ms", value_parser = duration::parse)]
+    backoff: Duration,
+
     /// Output as JSON
     #[arg(long)]
     json: bool,
 
-    /// Read targets from file (one per line)
+    /// Keep probing at --interval, printing only when a target goes up or
+    /// down, and show uptime and latency per target on exit (Ctrl-C)
+    #[arg(long)]
+    watch: bool,
+
+    /// Time between two rounds of --watch or two probes of --count
+    /// (default: 10s with --watch, 1s otherwise)
+    #[arg(long, value_parser = duration::parse)]
+    interval: Option<Duration>,
+
+    /// Stop --watch after this many rounds; without --watch, probe a single
+    /// target this many times like ping, with RTT and loss statistics
+    #[arg(long, value_parser = clap::value_parser!(u64).range(1..))]
+    count: Option<u64>,
+
+    /// Show a column per phase: DNS, connect, TLS, first byte and total time
+    #[arg(short, long)]
+    verbose: bool,
+
+    /// Read targets from file (one per line, optionally followed by key=value
+    /// options; `include other.txt` reads another file)
     #[arg(short, long)]
     file: Option<String>,
 
+    /// Read targets with per-target settings from a TOML or YAML file
+    #[arg(long, value_name = "FILE")]
+    config: Option<String>,
+
     /// Concurrent probe limit
     #[arg(short, long, default_value_t = 50)]
     concurrency: usize,
+
+    /// Which resolved addresses to probe
+    #[arg(long, value_enum, default_value_t = Addresses::First)]
+    addresses: Addresses,
+
+    /// Payload to send after connecting (supports \r, \n, \t, \0, \\ and \xHH)
+    #[arg(long)]
+    send: Option<String>,
+
+    /// Regex the response must match before the timeout
+    #[arg(long)]
+    expect: Option<String>,
+
+    /// Capture the greeting the server sends after connecting
+    #[arg(long, conflicts_with_all = ["send", "expect"])]
+    banner: bool,
+
+    /// Maximum banner size in bytes
+    #[arg(long, default_value_t = 256)]
+    banner_bytes: usize,
+
+    /// How long to wait for a banner
+    #[arg(long, default_value = "2s", value_parser = duration::parse)]
+    banner_wait: Duration,
+
+    /// Protocol check to run after connecting
+    #[arg(long, value_enum, default_value_t = CheckKind::Tcp, conflicts_with_all = ["send", "expect", "banner"])]
+    check: CheckKind,
+
+    /// Warn when a TLS certificate expires within this many days
+    #[arg(long)]
+    cert_warn_days: Option<u32>,
+
+    /// Fail when a TLS certificate expires within this many days
+    #[arg(long)]
+    cert_fail_days: Option<u32>,
+
+    /// Skip TLS certificate verification
+    #[arg(short = 'k', long)]
+    insecure: bool,
+
+    /// ALPN protocols to offer during the TLS handshake (comma-separated)
+    #[arg(long, value_delimiter = ',')]
+    alpn: Vec<String>,
+
+    /// Accepted HTTP status codes or ranges, e.g. 200-299,301 [default: 200-399]
+    #[arg(long, value_delimiter = ',')]
+    http_status: Vec<String>,
+
+    /// Required response header, optionally with a value it must contain ("Name: value")
+    #[arg(long)]
+    http_header: Vec<String>,
+
+    /// Substring the response body must contain
+    #[arg(long)]
+    http_body: Vec<String>,
+
+    /// JSON pointer that must exist in the body, optionally with a value ("/status=ok")
+    #[arg(long)]
+    http_json: Vec<String>,
+
+    /// Redis ACL username for AUTH
+    #[arg(long, requires = "redis_password")]
+    redis_user: Option<String>,
+
+    /// Redis password for AUTH
+    #[arg(long)]
+    redis_password: Option<String>,
+
+    /// Expected Redis replication role; replicas must also have their master link up
+    #[arg(long, value_parser = ["master", "replica"])]
+    redis_role: Option<String>,
+
+    /// User for a PostgreSQL StartupMessage (without it only SSLRequest is sent)
+    #[arg(long)]
+    pg_user: Option<String>,
+
+    /// Database for the PostgreSQL StartupMessage
+    #[arg(long, requires = "pg_user")]
+    pg_database: Option<String>,
+
+    /// Regex the SSH identification line must match
+    #[arg(long)]
+    ssh_banner: Option<String>,
+
+    /// Minimum OpenSSH version, e.g. 8.9 or 9.3p2
+    #[arg(long)]
+    ssh_min_openssh: Option<String>,
+
+    /// Run STARTTLS after EHLO and validate the certificate
+    #[arg(long)]
+    smtp_starttls: bool,
+
+    /// Name to send with EHLO
+    #[arg(long, default_value = "localhost")]
+    smtp_helo: String,
+}
+
+fn fail(message: impl std::fmt::Display) -> ! {
+    eprintln!("{} {}", "error:".red().bold(), message);
+    std::process::exit(1);
+}
+
+fn tls_check(args: &Args) -> Tls {
+    let mut tls = Tls::new().insecure(args.insecure).alpn(args.alpn.clone());
+    if let Some(days) = args.cert_warn_days {
+        tls = tls.cert_warn_days(days);
+    }
+    if let Some(days) = args.cert_fail_days {
+        tls = tls.cert_fail_days(days);
+    }
+    tls
 }
 
-#[derive(Debug, Serialize)]
-struct ProbeResult {
-    host: String,
-    status: String,
-    latency_ms: Option<f64>,
-    error: Option<String>,
-    retries_used: u32,
+/// The check every target runs after connecting, if any.
+fn build_check(args: &Args) -> Option<Arc<dyn Check>> {
+    if let Some(check) = send_expect_check(args) {
+        return Some(Arc::new(check));
+    }
+    if args.banner {
+        let banner = Banner::new()
+            .max_bytes(args.banner_bytes)
+            .window(args.banner_wait);
+        return Some(Arc::new(banner));
+    }
+    kind_check(args.check, args)
 }
 
-#[derive(Debug, Serialize)]
-struct Summary {
-    results: Vec<ProbeResult>,
-    healthy: usize,
-    total: usize,
+/// The check for `kind`, configured from the protocol options on the command line.
+fn kind_check(kind: CheckKind, args: &Args) -> Option<Arc<dyn Check>> {
+    match kind {
+        CheckKind::Tcp => None,
+        CheckKind::Tls => Some(Arc::new(tls_check(args))),
+        CheckKind::Redis => Some(Arc::new(redis_check(args))),
+        CheckKind::Postgres => Some(Arc::new(postgres_check(args))),
+        CheckKind::Mysql => Some(Arc::new(Mysql::new())),
+        CheckKind::Ssh => Some(Arc::new(ssh_check(args))),
+        CheckKind::Smtp => Some(Arc::new(smtp_check(args))),
+    }
 }
 
-fn parse_duration(s: &str) -> Duration {
-    let s = s.trim();
-    if let Some(secs) = s.strip_suffix('s') {
-        Duration::from_secs_f64(secs.parse().unwrap_or(5.0))
-    } else if let Some(ms) = s.strip_suffix("ms") {
-        Duration::from_millis(ms.parse().unwrap_or(5000))
+fn smtp_check(args: &Args) -> Smtp {
+    let smtp = Smtp::new().helo_name(args.smtp_helo.clone());
+    if args.smtp_starttls {
+        smtp.starttls(tls_check(args))
     } else {
-        Duration::from_secs(s.parse().unwrap_or(5))
+        smtp
     }
 }
 
-async fn probe_host(host: &str, connect_timeout: Duration, retries: u32) -> ProbeResult {
-    let mut last_error = None;
-    let mut retries_used = 0;
+fn ssh_check(args: &Args) -> Ssh {
+    let mut ssh = Ssh::new();
+    if let Some(pattern) = &args.ssh_banner {
+        match regex::Regex::new(pattern) {
+            Ok(re) => ssh = ssh.expect(re),
+            Err(e) => fail(format!("Invalid --ssh-banner regex: {}", e)),
+        }
+    }
+    if let Some(version) = &args.ssh_min_openssh {
+        ssh = ssh.min_openssh(version).unwrap_or_else(|e| fail(e));
+    }
+    ssh
+}
 
-    for attempt in 0..=retries {
-        if attempt > 0 {
-            retries_used = attempt;
-            tokio::time::sleep(Duration::from_millis(100 * attempt as u64)).await;
+fn postgres_check(args: &Args) -> Postgres {
+    let mut postgres = Postgres::new();
+    if let Some(user) = &args.pg_user {
+        postgres = postgres.user(user.clone());
+    }
+    if let Some(database) = &args.pg_database {
+        postgres = postgres.database(database.clone());
+    }
+    postgres
+}
+
+fn redis_check(args: &Args) -> Redis {
+    let mut redis = Redis::new();
+    if let Some(password) = &args.redis_password {
+        redis = redis.auth(args.redis_user.clone(), password.clone());
+    }
+    match args.redis_role.as_deref() {
+        Some("master") => redis.role(RedisRole::Master),
+        Some(_) => redis.role(RedisRole::Replica),
+        None => redis,
+    }
+}
+
+fn parse_status_range(s: &str) -> Result<std::ops::RangeInclusive<u16>, String> {
+    let parse = |v: &str| {
+        v.trim()
+            .parse::<u16>()
+            .map_err(|_| format!("invalid HTTP status '{}'", s))
+    };
+    match s.split_once('-') {
+        Some((lo, hi)) => Ok(parse(lo)?..=parse(hi)?),
+        None => parse(s).map(|code| code..=code),
+    }
+}
+
+/// HTTP assertions shared by every http:// and https:// target.
+fn http_check(args: &Args) -> Http {
+    let mut http = Http::new();
+    for status in &args.http_status {
+        match parse_status_range(status) {
+            Ok(range) => http = http.expect_status(range),
+            Err(e) => fail(e),
         }
+    }
+    for header in &args.http_header {
+        http = match header.split_once(':') {
+            Some((name, value)) => http.require_header(name.trim(), Some(value.trim().to_string())),
+            None => http.require_header(header.trim(), None),
+        };
+    }
+    for needle in &args.http_body {
+        http = http.body_contains(needle.clone());
+    }
+    for pointer in &args.http_json {
+        http = match pointer.split_once('=') {
+            Some((pointer, value)) => http.json_pointer(pointer, Some(value.to_string())),
+            None => http.json_pointer(pointer.clone(), None),
+        };
+    }
+    http
+}
 
-        // Resolve DNS first
-        let addr = match host.to_socket_addrs() {
-            Ok(mut addrs) => match addrs.next() {
-                Some(a) => a,
-                None => {
-                    last_error = Some("DNS resolution failed: no addresses".to_string());
-                    continue;
-                }
-            },
-            Err(e) => {
-                last_error = Some(format!("DNS error: {}", e));
-                continue;
+/// URL schemes [`url_target`] has a check for.
+const URL_SCHEMES: [&str; 11] = [
+    "http",
+    "https",
+    "postgres",
+    "postgresql",
+    "redis",
+    "mysql",
+    "mariadb",
+    "ssh",
+    "smtp",
+    "tls",
+    "tcp",
+];
+
+/// Parse a URL target, making sure its scheme is supported and it has a port.
+fn parse_url(raw: &str) -> Result<(TargetUrl, u16), String> {
+    let url = TargetUrl::parse(raw).map_err(|e| e.to_string())?;
+    if !URL_SCHEMES.contains(&url.scheme.as_str()) {
+        return Err(format!(
+            "Unsupported URL scheme '{}' in {}",
+            url.scheme, raw
+        ));
+    }
+    if url.scheme.starts_with("postgres") {
+        url.database().map_err(|e| e.to_string())?;
+    }
+    let port = url.port.or_else(|| url.default_port()).ok_or_else(|| {
+        format!(
+            "Missing port in '{}': {}:// has no default port",
+            raw, url.scheme
+        )
+    })?;
+    Ok((url, port))
+}
+
+/// Target for a URL, with the check its scheme implies. Credentials and the
+/// database in the URL take precedence over the matching command-line options.
+fn url_target(raw: &str, http: &Http, args: &Args) -> Target {
+    let (url, port) = parse_url(raw).unwrap_or_else(|e| fail(e));
+    let check: Option<Arc<dyn Check>> = match url.scheme.as_str() {
+        "http" => Some(Arc::new(http.clone().path(&url.path))),
+        "https" => Some(Arc::new(
+            http.clone()
+                .path(&url.path)
+                .tls(tls_check(args).alpn(["http/1.1"])),
+        )),
+        "postgres" | "postgresql" => {
+            let mut postgres = postgres_check(args);
+            if let Some(user) = &url.user {
+                postgres = postgres.user(user.clone());
             }
-        };
+            if let Some(database) = url.database().unwrap_or_else(|e| fail(e)) {
+                postgres = postgres.database(database);
+            }
+            Some(Arc::new(postgres))
+        }
+        "redis" => {
+            let mut redis = redis_check(args);
+            if let Some(password) = &url.password {
+                // redis://:password@host authenticates as the default user.
+                let user = url.user.clone().filter(|u| !u.is_empty());
+                redis = redis.auth(user, password.clone());
+            }
+            Some(Arc::new(redis))
+        }
+        "mysql" | "mariadb" => Some(Arc::new(Mysql::new())),
+        "ssh" => Some(Arc::new(ssh_check(args))),
+        "smtp" => Some(Arc::new(smtp_check(args))),
+        "tls" => Some(Arc::new(tls_check(args))),
+        "tcp" => None,
+        _ => unreachable!("scheme checked by parse_url"),
+    };
+
+    let label = match url.password {
+        Some(_) => url.redacted(),
+        None => raw.to_string(),
+    };
+    Target {
+        check,
+        ..Target::new(url.addr(port)).with_label(label)
+    }
+}
+
+/// A command-line target, possibly standing for several probes reported together.
+#[derive(Clone)]
+enum Entry {
+    Single(Target),
+    Group {
+        name: String,
+        rule: MinHealthy,
+        members: Vec<(Target, Map<String, Value>)>,
+        /// Supplies the group's own tags and description.
+        template: Target,
+    },
+    /// Expansion failed; reported as is.
+    Done(Box<ProbeResult>),
+}
 
-        let start = Instant::now();
-        match timeout(connect_timeout, TcpStream::connect(addr)).await {
-            Ok(Ok(_stream)) => {
-                let elapsed = start.elapsed();
-                return ProbeResult {
-                    host: host.to_string(),
-                    status: "ok".to_string(),
-                    latency_ms: Some(elapsed.as_secs_f64() * 1000.0),
-                    error: None,
-                    retries_used,
-                };
+/// Expand `srv:_service._proto.name` into its hosts, ordered by priority and weight.
+async fn srv_entry(prober: &Prober, name: &str, template: &Target, args: &Args) -> Entry {
+    let group = format!("srv:{}", name);
+    let rule = args.min_healthy.unwrap_or(MinHealthy::Count(1));
+    let records = match prober.lookup_srv(name).await {
+        Ok(records) => records,
+        Err(e) => return Entry::Done(Box::new(failed_group(group, rule, e, template))),
+    };
+    // RFC 2782: a single record with target "." means the service is not offered.
+    if records.iter().all(|r| r.target == ".") {
+        let error = format!("{}: service explicitly not available", name);
+        return Entry::Done(Box::new(failed_group(group, rule, error, template)));
+    }
+
+    let members = records
+        .into_iter()
+        .filter(|r| r.target != ".")
+        .map(|r| {
+            let target = member(template, format!("{}:{}", r.target, r.port));
+            let mut details = Map::new();
+            details.insert("srv_priority".to_string(), r.priority.into());
+            details.insert("srv_weight".to_string(), r.weight.into());
+            (target, details)
+        })
+        .collect();
+    Entry::Group {
+        name: group,
+        rule,
+        members,
+        template: template.clone(),
+    }
+}
+
+/// Split `host:80,443,8000-8010` into host and port list; `None` for a single port.
+fn multi_port(addr: &str) -> Option<(&str, &str)> {
+    let (host, ports) = addr.rsplit_once(':')?;
+    if ports.contains([',', '-']) {
+        Some((host, ports))
+    } else {
+        None
+    }
+}
+
+/// `80,443,8000-8010` in the order given, without duplicates.
+fn parse_ports(spec: &str) -> Result<Vec<u16>, String> {
+    let port = |s: &str| {
+        s.trim()
+            .parse::<u16>()
+            .ok()
+            .filter(|&p| p != 0)
+            .ok_or_else(|| format!("invalid port '{}'", s))
+    };
+
+    let mut ports = Vec::new();
+    for part in spec.split(',') {
+        let range = match part.split_once('-') {
+            Some((start, end)) => {
+                let (start, end) = (port(start)?, port(end)?);
+                if start > end {
+                    return Err(format!("empty range '{}'", part));
+                }
+                start..=end
             }
-            Ok(Err(e)) => {
-                last_error = Some(format!("Connection refused: {}", e));
+            None => {
+                let p = port(part)?;
+                p..=p
             }
-            Err(_) => {
-                last_error = Some(format!("timeout ({}ms)", connect_timeout.as_millis()));
+        };
+        for p in range {
+            if !ports.contains(&p) {
+                ports.push(p);
+            }
+        }
+    }
+    Ok(ports)
+}
+
+/// Check a target string the way [`build_entry`] will read it, without
+/// resolving or expanding anything, so files can report mistakes by line.
+fn check_addr(addr: &str) -> Result<(), String> {
+    if addr.is_empty() {
+        return Err("empty target address".to_string());
+    }
+    if let Some(name) = addr.strip_prefix("srv:") {
+        if name.is_empty() {
+            return Err("missing name after 'srv:'".to_string());
+        }
+        return Ok(());
+    }
+    if TargetUrl::is_url(addr) {
+        return parse_url(addr).map(drop);
+    }
+    let Some((host, ports)) = addr
+        .rsplit_once(':')
+        .filter(|(host, ports)| !host.is_empty() && !ports.is_empty())
+    else {
+        return Err(format!(
+            "missing port in '{}': use host:port or a URL",
+            addr
+        ));
+    };
+    if expand::is_pattern(host) {
+        drop(expand::hosts(host)?);
+    }
+    parse_ports(ports).map_err(|e| format!("Invalid ports in '{}': {}", addr, e))?;
+    Ok(())
+}
+
+fn failed_group(name: String, rule: MinHealthy, error: String, template: &Target) -> ProbeResult {
+    let mut result = group_result(name, Vec::new(), rule, template);
+    result.error = Some(error);
+    result
+}
+
+fn group_result(
+    name: String,
+    members: Vec<ProbeResult>,
+    rule: MinHealthy,
+    template: &Target,
+) -> ProbeResult {
+    let mut result = ProbeResult::group(name, members, rule);
+    result.tags = template.tags.clone();
+    result.description = template.description.clone();
+    result
+}
+
+/// `template` probing `addr` instead: same check, limits, tags and description.
+fn member(template: &Target, addr: String) -> Target {
+    Target {
+        addr,
+        ..template.clone()
+    }
+}
+
+/// Probe every target of every entry in one batch, then put groups back together.
+async fn probe_entries(prober: &Prober, entries: Vec<Entry>) -> Summary {
+    let targets: Vec<Target> = entries
+        .iter()
+        .flat_map(|entry| match entry {
+            Entry::Single(target) => vec![target.clone()],
+            Entry::Group { members, .. } => members.iter().map(|(t, _)| t.clone()).collect(),
+            Entry::Done(_) => Vec::new(),
+        })
+        .collect();
+    let mut results = prober.probe_many(targets).await.results.into_iter();
+
+    let total = entries.len();
+    let mut grouped = Vec::with_capacity(total);
+    for entry in entries {
+        grouped.push(match entry {
+            Entry::Single(_) => results.next().expect("one result per target"),
+            Entry::Group {
+                name,
+                rule,
+                members,
+                template,
+            } => {
+                let members = members
+                    .into_iter()
+                    .map(|(_, details)| {
+                        let mut result = results.next().expect("one result per target");
+                        result.details.extend(details);
+                        result
+                    })
+                    .collect();
+                group_result(name, members, rule, &template)
             }
+            Entry::Done(result) => *result,
+        });
+    }
+    Summary::new(grouped, total)
+}
+
+fn send_expect_check(args: &Args) -> Option<SendExpect> {
+    if args.send.is_none() && args.expect.is_none() {
+        return None;
+    }
+
+    let mut check = SendExpect::new();
+    if let Some(send) = &args.send {
+        match unescape(send) {
+            Ok(payload) => check = check.send(payload),
+            Err(e) => fail(format!("Invalid --send payload: {}", e)),
+        }
+    }
+    if let Some(expect) = &args.expect {
+        match regex::bytes::Regex::new(expect) {
+            Ok(re) => check = check.expect(re),
+            Err(e) => fail(format!("Invalid --expect regex: {}", e)),
         }
     }
+    Some(check)
+}
+
+/// `1.1.1.1`, `1.1.1.1:5353`, `::1` or `[::1]:5353`; the port defaults to 53.
+fn parse_dns_server(s: &str) -> SocketAddr {
+    s.parse::<SocketAddr>()
+        .or_else(|_| s.parse::<IpAddr>().map(|ip| SocketAddr::new(ip, 53)))
+        .unwrap_or_else(|_| fail(format!("Invalid --dns-server '{}'", s)))
+}
+
+/// curl-style `host:port:addr[,addr...]`, with IPv6 addresses optionally in brackets.
+fn parse_resolve(s: &str) -> Option<(&str, u16, Vec<IpAddr>)> {
+    let (host, rest) = s.split_once(':')?;
+    let (port, addrs) = rest.split_once(':')?;
+    let addrs = addrs
+        .split(',')
+        .map(|a| a.trim_start_matches('[').trim_end_matches(']').parse().ok())
+        .collect::<Option<Vec<IpAddr>>>()?;
+    Some((host, port.parse().ok()?, addrs))
+}
+
+fn protocol_summary(result: &ProbeResult) -> String {
+    let mut summary = String::new();
+    if let Some(tls) = &result.tls {
+        summary.push_str(&tls_summary(tls));
+    }
+    if let Some(http) = &result.http {
+        summary.push_str(&format!(
+            "  HTTP {} ttfb {:.1}ms total {:.1}ms",
+            http.status, http.ttfb_ms, http.total_ms
+        ));
+    }
+    summary
+}
 
-    ProbeResult {
-        host: host.to_string(),
-        status: "fail".to_string(),
-        latency_ms: None,
-        error: last_error,
-        retries_used,
+fn tls_summary(tls: &TlsInfo) -> String {
+    let mut summary = format!("  {} +{:.1}ms", tls.version, tls.handshake_ms);
+    if let Some(alpn) = &tls.alpn {
+        summary.push_str(&format!(" {}", alpn));
+    }
+    if let Some(days) = tls.days_left {
+        summary.push_str(&format!(", cert expires in {}d", days));
     }
+    summary
 }
 
 fn print_result(result: &ProbeResult) {
-    if result.status == "ok" {
+    if result.status == Status::Warn {
+        let warning = result.warning.as_deref().unwrap_or("warning");
+        println!(
+            "{} {:<30} {:.1}ms{}  {}",
+            "[WARN]".yellow().bold(),
+            result.host,
+            result.latency_ms.unwrap_or(0.0),
+            protocol_summary(result),
+            warning.yellow()
+        );
+    } else if result.is_healthy() {
         let latency = result.latency_ms.unwrap_or(0.0);
         let retries_info = if result.retries_used > 0 {
             format!(" (retries: {})", result.retries_used)
         } else {
             String::new()
         };
+        let banner = match &result.banner {
+            Some(banner) => format!("  {}", format!("\"{}\"", banner).dimmed()),
+            None => String::new(),
+        };
         println!(
-            "{} {:<30} {:.1}ms{}",
+            "{} {:<30} {:.1}ms{}{}{}",
             "[OK]  ".green().bold(),
             result.host,
             latency,
-            retries_info
+            protocol_summary(result),
+            retries_info,
+            banner
+        );
+    } else if result.status == Status::Starting {
+        let error = result.error.as_deref().unwrap_or("starting");
+        println!(
+            "{} {:<30} {}",
+            "[INIT]".yellow().bold(),
+            result.host,
+            error.yellow()
         );
     } else {
         let error = result.error.as_deref().unwrap_or("unknown");
@@ -140,81 +740,427 @@ fn print_result(result: &ProbeResult) {
             error.red()
         );
     }
+    print_sub_results(result);
+
+    for member in &result.members {
+        let outcome = match (member.status, member.latency_ms) {
+            (Status::Ok, Some(latency)) => format!("{:.1}ms", latency),
+            (Status::Warn, _) => member
+                .warning
+                .as_deref()
+                .unwrap_or("warning")
+                .yellow()
+                .to_string(),
+            (_, _) => member
+                .error
+                .as_deref()
+                .unwrap_or("unknown")
+                .red()
+                .to_string(),
+        };
+        println!(
+            "       {} {:<30} {}",
+            status_label(member.status),
+            member.host,
+            outcome
+        );
+    }
 }
 
-#[tokio::main]
-async fn main() {
-    let args = Args::parse();
-    let connect_timeout = parse_duration(&args.timeout);
+/// Column headings matching [`print_verbose`].
+fn print_verbose_header() {
+    println!(
+        "{}",
+        format!(
+            "{:<6} {:<30} {:>9} {:>9} {:>9} {:>9} {:>9}",
+            "", "HOST", "DNS", "CONNECT", "TLS", "1ST BYTE", "TOTAL"
+        )
+        .dimmed()
+    );
+}
 
-    // Collect all targets
-    let mut targets: Vec<String> = args.targets.clone();
-    if let Some(file_path) = &args.file {
-        match fs::read_to_string(file_path) {
-            Ok(content) => {
-                for line in content.lines() {
-                    let line = line.trim();
-                    if !line.is_empty() && !line.starts_with('#') {
-                        targets.push(line.to_string());
-                    }
+fn status_label(status: Status) -> ColoredString {
+    match status {
+        Status::Ok => "[OK]  ".green().bold(),
+        Status::Warn => "[WARN]".yellow().bold(),
+        Status::Starting => "[INIT]".yellow().bold(),
+        Status::Fail => "[FAIL]".red().bold(),
+    }
+}
+
+fn print_verbose(result: &ProbeResult) {
+    print_verbose_row(result, "");
+    for member in &result.members {
+        print_verbose_row(member, "  ");
+    }
+}
+
+fn print_verbose_row(result: &ProbeResult, indent: &str) {
+    let column = |ms: Option<f64>| match ms {
+        Some(ms) => format!("{:>7.1}ms", ms),
+        None => format!("{:>9}", "-"),
+    };
+    let message = match result.status {
+        Status::Ok => match &result.http {
+            Some(http) => format!("HTTP {}", http.status),
+            None => String::new(),
+        },
+        Status::Warn => result
+            .warning
+            .as_deref()
+            .unwrap_or("warning")
+            .yellow()
+            .to_string(),
+        Status::Starting => result
+            .error
+            .as_deref()
+            .unwrap_or("starting")
+            .yellow()
+            .to_string(),
+        Status::Fail => result
+            .error
+            .as_deref()
+            .unwrap_or("unknown")
+            .red()
+            .to_string(),
+    };
+    let timings = &result.timings;
+    let line = format!(
+        "{} {:<30} {} {} {} {} {}  {}",
+        status_label(result.status),
+        format!("{}{}", indent, result.host),
+        column(timings.dns_ms),
+        column(timings.connect_ms),
+        column(timings.tls_ms),
+        column(timings.first_byte_ms),
+        column(Some(timings.total_ms)),
+        message
+    );
+    println!("{}", line.trim_end());
+    print_sub_results(result);
+}
+
+/// Indented lines for Happy Eyeballs attempts and per-address results.
+fn print_sub_results(result: &ProbeResult) {
+    if let Some(race) = &result.happy_eyeballs {
+        for attempt in &race.attempts {
+            let timing = match (attempt.started_ms, attempt.elapsed_ms) {
+                (Some(started), Some(elapsed)) => format!("+{:.0}ms {:.1}ms", started, elapsed),
+                (Some(started), None) => format!("+{:.0}ms", started),
+                _ => String::new(),
+            };
+            let outcome = match attempt.outcome {
+                "won" => attempt.outcome.green().to_string(),
+                "failed" => attempt.outcome.red().to_string(),
+                other => other.dimmed().to_string(),
+            };
+            println!(
+                "         {:<30} {} {} {}",
+                attempt.addr, attempt.family, outcome, timing
+            );
+        }
+    }
+
+    for address in &result.addresses {
+        match (address.latency_ms, &address.error) {
+            (Some(latency), _) => println!("         {:<30} {:.1}ms", address.addr, latency),
+            (None, error) => println!(
+                "         {:<30} {}",
+                address.addr,
+                error.as_deref().unwrap_or("unknown").red()
+            ),
+        }
+    }
+}
+
+/// Settings for a config or targets file target: its own first, then the
+/// file's `[defaults]`, then the command line. Tags from both are combined.
+fn config_template(
+    file_defaults: &Defaults,
+    target: &config::TargetConfig,
+    cli: &Target,
+    args: &Args,
+) -> Target {
+    let check = match target.check.or(file_defaults.check) {
+        Some(kind) => kind_check(kind, args),
+        None => cli.check.clone(),
+    };
+    let mut tags = file_defaults.tags.clone();
+    tags.extend(target.tags.iter().cloned());
+    Target {
+        check,
+        timeout: target.timeout.or(file_defaults.timeout),
+        retries: target.retries.or(file_defaults.retries),
+        tags,
+        description: target.description.clone(),
+        ..Target::new(target.addr.clone())
+    }
+}
+
+/// Turn a target string into what it stands for, every probe copying `template`.
+/// `expanded` counts the probes earlier expansions produced, for the
+/// `--max-targets` budget.
+async fn build_entry(
+    addr: String,
+    template: &Target,
+    prober: &Prober,
+    http: &Http,
+    args: &Args,
+    expanded: usize,
+) -> Entry {
+    if let Some(name) = addr.strip_prefix("srv:") {
+        srv_entry(prober, name, template, args).await
+    } else if TargetUrl::is_url(&addr) {
+        // The scheme decides the check; everything else comes from the template.
+        let target = url_target(&addr, http, args);
+        Entry::Single(Target {
+            label: target.label,
+            check: target.check,
+            ..member(template, target.addr)
+        })
+    } else if let Some((host, ports)) = addr.rsplit_once(':').filter(|(h, _)| expand::is_pattern(h))
+    {
+        let ports = parse_ports(ports)
+            .unwrap_or_else(|e| fail(format!("Invalid ports in '{}': {}", addr, e)));
+        let hosts = expand::hosts(host).unwrap_or_else(|e| fail(e));
+        // Stop one past the budget: enough to know it is exceeded.
+        let budget = args.max_targets.saturating_sub(expanded).saturating_add(1);
+        let members = hosts
+            .flat_map(|host| ports.iter().map(move |port| format!("{}:{}", host, port)))
+            .take(budget)
+            .map(|addr| (member(template, addr), Map::new()))
+            .collect();
+        Entry::Group {
+            name: addr.clone(),
+            rule: args.min_healthy.unwrap_or(MinHealthy::All),
+            members,
+            template: template.clone(),
+        }
+    } else if let Some((host, ports)) = multi_port(&addr) {
+        let ports = parse_ports(ports)
+            .unwrap_or_else(|e| fail(format!("Invalid ports in '{}': {}", addr, e)));
+        let members = ports
+            .into_iter()
+            .map(|port| (member(template, format!("{}:{}", host, port)), Map::new()))
+            .collect();
+        Entry::Group {
+            name: host.to_string(),
+            rule: args.min_healthy.unwrap_or(MinHealthy::All),
+            members,
+            template: template.clone(),
+        }
+    } else {
+        let target = member(template, addr);
+        if target.port().is_none() {
+            match target.addr.rsplit_once(':') {
+                Some((_, port)) if !target.addr.ends_with(']') => {
+                    fail(format!("Invalid port '{}' in '{}'", port, target.addr))
                 }
-            }
-            Err(e) => {
-                eprintln!("{} Failed to read file: {}", "error:".red().bold(), e);
-                std::process::exit(1);
+                _ => fail(format!(
+                    "Missing port in '{}': use host:port, or a URL such as https://{}",
+                    target.addr, target.addr
+                )),
             }
         }
+        Entry::Single(target)
     }
+}
 
-    if targets.is_empty() {
-        eprintln!("{} No targets specified", "error:".red().bold());
-        std::process::exit(1);
+/// Build the entries for `targets`. Plain targets are not capped; only the
+/// probes that patterns, port lists and SRV records expand to count against
+/// `--max-targets`.
+async fn build_entries(
+    targets: &[(String, Target)],
+    prober: &Prober,
+    http: &Http,
+    args: &Args,
+) -> Vec<Entry> {
+    let mut entries = Vec::with_capacity(targets.len());
+    let mut expanded = 0;
+    for (addr, template) in targets {
+        let entry = build_entry(addr.clone(), template, prober, http, args, expanded).await;
+        if let Entry::Group { members, .. } = &entry {
+            expanded += members.len();
+        }
+        if expanded > args.max_targets {
+            fail(format!(
+                "Targets expand to more than {} probes; raise --max-targets to run them all",
+                args.max_targets
+            ));
+        }
+        entries.push(entry);
     }
+    entries
+}
+
+/// `--interval`, or `default` without it.
+fn interval(args: &Args, default: Duration) -> Duration {
+    match args.interval {
+        Some(interval) if interval.is_zero() => fail("--interval must be greater than zero"),
+        Some(interval) => interval,
+        None => default,
+    }
+}
 
-    // Run probes concurrently
-    let semaphore = std::sync::Arc::new(tokio::sync::Semaphore::new(args.concurrency));
-    let mut handles = Vec::new();
+#[tokio::main]
+async fn main() {
+    let args = Args::parse();
 
-    for target in &targets {
-        let sem = semaphore.clone();
-        let target = target.clone();
-        let retries = args.retries;
+    // Collect all targets, each with the settings it starts from
+    let defaults = Target {
+        check: build_check(&args),
+        ..Target::new("")
+    };
+    let mut targets: Vec<(String, Target)> = args
+        .targets
+        .iter()
+        .map(|addr| (addr.clone(), defaults.clone()))
+        .collect();
+    if let Some(file_path) = &args.file {
+        let targets_file = config::read_targets_file(file_path).unwrap_or_else(|e| fail(e));
+        let no_defaults = Defaults::default();
+        for target in &targets_file {
+            targets.push((
+                target.addr.clone(),
+                config_template(&no_defaults, target, &defaults, &args),
+            ));
+        }
+    }
+    if let Some(path) = &args.config {
+        let config = Config::load(path).unwrap_or_else(|e| fail(e));
+        for target in &config.targets {
+            targets.push((
+                target.addr.clone(),
+                config_template(&config.defaults, target, &defaults, &args),
+            ));
+        }
+    }
 
-        handles.push(tokio::spawn(async move {
-            let _permit = sem.acquire().await.unwrap();
-            probe_host(&target, connect_timeout, retries).await
-        }));
+    if targets.is_empty() {
+        fail("No targets specified");
     }
 
-    let mut results = Vec::new();
-    for handle in handles {
-        if let Ok(result) = handle.await {
-            results.push(result);
+    let mut prober = Prober::new()
+        .timeout(args.timeout)
+        .retries(args.retries)
+        .backoff(args.backoff)
+        .concurrency(args.concurrency)
+        .addresses(match args.addresses {
+            Addresses::First => AddressMode::First,
+            Addresses::All => AddressMode::All,
+            Addresses::Any => AddressMode::Any,
+            Addresses::HappyEyeballs => AddressMode::HappyEyeballs,
+        });
+    if let Some(dns_timeout) = args.dns_timeout {
+        prober = prober.dns_timeout(dns_timeout);
+    }
+    if let Some(server) = &args.dns_server {
+        prober = prober.dns_server(parse_dns_server(server));
+    }
+    for pin in &args.resolve {
+        let (host, port, addrs) = parse_resolve(pin).unwrap_or_else(|| {
+            fail(format!(
+                "Invalid --resolve '{}': expected host:port:addr",
+                pin
+            ))
+        });
+        for addr in addrs {
+            prober = prober.resolve_override(host, port, addr);
         }
     }
 
-    let healthy = results.iter().filter(|r| r.status == "ok").count();
+    let http = http_check(&args);
+    let entries = build_entries(&targets, &prober, &http, &args).await;
+    if args.watch {
+        let interval = interval(&args, Duration::from_secs(10));
+        let healthy = watch::run(&prober, &targets, entries, &args, interval).await;
+        std::process::exit(if healthy { 0 } else { 1 });
+    }
+    if let Some(count) = args.count {
+        let target = match entries.as_slice() {
+            [Entry::Single(target)] => target.clone(),
+            _ => fail("--count without --watch takes a single host:port or URL target"),
+        };
+        let interval = interval(&args, Duration::from_secs(1));
+        let replied = ping::run(&prober, target, count, interval, args.json).await;
+        std::process::exit(if replied { 0 } else { 1 });
+    }
+    if args.interval.is_some() {
+        fail("--interval needs --watch or --count");
+    }
+    let summary = probe_entries(&prober, entries).await;
 
     if args.json {
-        let summary = Summary {
-            results,
-            healthy,
-            total: targets.len(),
-        };
         println!("{}", serde_json::to_string_pretty(&summary).unwrap());
     } else {
-        for result in &results {
-            print_result(result);
+        if args.verbose {
+            print_verbose_header();
+        }
+        for result in &summary.results {
+            if args.verbose {
+                print_verbose(result);
+            } else {
+                print_result(result);
+            }
         }
+        let warnings = match summary.warnings {
+            0 => String::new(),
+            1 => " (1 warning)".to_string(),
+            n => format!(" ({} warnings)", n),
+        };
         println!(
-            "\n{}: {}/{} healthy",
+            "\n{}: {}/{} healthy{}",
             "Summary".bold(),
-            healthy,
-            targets.len()
+            summary.healthy,
+            summary.total,
+            warnings
         );
     }
 
-    if healthy < targets.len() {
+    if !summary.all_healthy() {
         std::process::exit(1);
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_ports_keeps_order_and_drops_duplicates() {
+        assert_eq!(parse_ports("443,80,443"), Ok(vec![443, 80]));
+        assert_eq!(
+            parse_ports("8000-8002,8001,22"),
+            Ok(vec![8000, 8001, 8002, 22])
+        );
+        assert_eq!(parse_ports("5432"), Ok(vec![5432]));
+    }
+
+    #[test]
+    fn parse_ports_rejects_bad_ports_and_ranges() {
+        for spec in ["", "0", "65536", "http", "80,", "80-", "-80", "90-80"] {
+            assert!(parse_ports(spec).is_err(), "{:?} should be rejected", spec);
+        }
+    }
+
+    #[test]
+    fn multi_port_only_splits_lists_and_ranges() {
+        assert_eq!(multi_port("fw:22,443"), Some(("fw", "22,443")));
+        assert_eq!(multi_port("fw:8000-8010"), Some(("fw", "8000-8010")));
+        assert_eq!(multi_port("fw:22"), None);
+    }
+
+    #[tokio::test]
+    async fn plain_targets_do_not_count_against_max_targets() {
+        let args = Args::parse_from(["tcp-probe", "--max-targets", "4"]);
+        let targets: Vec<_> = (1..=1030)
+            .map(|port| {
+                let addr = format!("127.0.0.1:{}", port);
+                (addr.clone(), Target::new(addr))
+            })
+            .collect();
+        let entries = build_entries(&targets, &Prober::new(), &http_check(&args), &args).await;
+        assert_eq!(entries.len(), 1030);
+        assert!(entries.iter().all(|e| matches!(e, Entry::Single(_))));
+    }
+}
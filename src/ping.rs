@@ -0,0 +1,125 @@
+//! `--count` on a single target: connect again and again like `ping`, printing
+//! each round trip and the loss and latency statistics at the end.
+
+use colored::Colorize;
+use serde_json::json;
+use std::time::{Duration, Instant};
+use tcp_probe::duration;
+use tcp_probe::{Prober, Target};
+use tokio::time::MissedTickBehavior;
+
+use crate::stats::Latency;
+use crate::watch::timestamp;
+
+/// Probe `target` `count` times, `interval` apart, stopping early on Ctrl-C.
+/// Returns whether any probe succeeded, as `ping` does.
+pub async fn run(
+    prober: &Prober,
+    target: Target,
+    count: u64,
+    interval: Duration,
+    json: bool,
+) -> bool {
+    let name = target.name().to_string();
+    if !json {
+        println!("PROBE {} ({})", name, check_name(&target));
+    }
+
+    let started = Instant::now();
+    let mut latency = Latency::default();
+    let (mut sent, mut received) = (0, 0);
+    let mut ticker = tokio::time::interval(interval);
+    ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+    while sent < count {
+        let result = tokio::select! {
+            _ = tokio::signal::ctrl_c() => break,
+            result = async {
+                ticker.tick().await;
+                prober.probe(target.clone()).await
+            } => result,
+        };
+        sent += 1;
+        let rtt = result.latency_ms.filter(|_| result.is_healthy());
+        if let Some(ms) = rtt {
+            received += 1;
+            latency.add(ms);
+        }
+
+        if json {
+            let mut event = serde_json::to_value(&result).unwrap();
+            event["seq"] = sent.into();
+            event["timestamp"] = timestamp().into();
+            println!("{}", event);
+        } else {
+            match rtt {
+                Some(ms) => println!("{}: seq={} time={:.2}ms", name, sent, ms),
+                None => println!(
+                    "{}: seq={} {}",
+                    name,
+                    sent,
+                    result.error.as_deref().unwrap_or("unknown").red()
+                ),
+            }
+        }
+    }
+
+    print_stats(&name, sent, received, &latency, started.elapsed(), json);
+    received > 0
+}
+
+fn check_name(target: &Target) -> &str {
+    target.check.as_ref().map_or("tcp", |check| check.name())
+}
+
+fn print_stats(
+    name: &str,
+    sent: u64,
+    received: u64,
+    latency: &Latency,
+    elapsed: Duration,
+    json: bool,
+) {
+    let loss = match sent {
+        0 => 0.0,
+        _ => (sent - received) as f64 * 100.0 / sent as f64,
+    };
+
+    if json {
+        let stats = json!({
+            "host": name,
+            "probes": sent,
+            "ok": received,
+            "loss_pct": loss,
+            "elapsed_ms": elapsed.as_secs_f64() * 1000.0,
+            "rtt_min_ms": latency.min(),
+            "rtt_avg_ms": latency.avg(),
+            "rtt_max_ms": latency.max(),
+            "rtt_mdev_ms": latency.mdev(),
+        });
+        println!("{}", stats);
+        return;
+    }
+
+    println!("\n--- {} probe statistics ---", name);
+    let summary = format!(
+        "{} probes, {} ok, {:.1}% loss, time {}",
+        sent,
+        received,
+        loss,
+        duration::format(elapsed)
+    );
+    if received == sent {
+        println!("{}", summary);
+    } else {
+        println!("{}", summary.red());
+    }
+    if let (Some(min), Some(avg), Some(max), Some(mdev)) =
+        (latency.min(), latency.avg(), latency.max(), latency.mdev())
+    {
+        println!(
+            "rtt min/avg/max/mdev = {:.3}/{:.3}/{:.3}/{:.3} ms",
+            min, avg, max, mdev
+        );
+    }
+}
@@ -0,0 +1,666 @@
+use std::collections::HashMap;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::net::{lookup_host, TcpStream};
+use tokio::sync::Semaphore;
+use tokio::task::JoinSet;
+use tokio::time::timeout;
+
+use crate::check::{CheckContext, CheckOutcome};
+use crate::dns::{self, SrvRecord};
+use crate::happy_eyeballs::{self, CONNECTION_ATTEMPT_DELAY};
+use crate::result::{AddressResult, ProbeResult, Status, Summary, Timings};
+use crate::target::Target;
+
+/// Extra time a check gets beyond its own timeout to report why it stalled.
+const CHECK_GRACE: Duration = Duration::from_millis(250);
+
+/// Which of the addresses a name resolves to get probed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum AddressMode {
+    /// Only the first resolved address.
+    #[default]
+    First,
+    /// Every address; any dead one fails the target.
+    All,
+    /// Every address; dead ones only warn as long as one is healthy.
+    Any,
+    /// Race IPv6 and IPv4 connects with staggered starts (RFC 8305) and
+    /// check the connection that wins, like dual-stack clients do.
+    HappyEyeballs,
+}
+
+/// Configures and runs TCP probes.
+///
+/// ```no_run
+/// # async fn run() {
+/// use std::time::Duration;
+/// use tcp_probe::Prober;
+///
+/// let summary = Prober::new()
+///     .timeout(Duration::from_secs(2))
+///     .retries(1)
+///     .probe_many(["db.internal:5432", "redis:6379"])
+///     .await;
+/// assert!(summary.all_healthy());
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct Prober {
+    timeout: Duration,
+    retries: u32,
+    backoff: Duration,
+    concurrency: usize,
+    addresses: AddressMode,
+    dns_timeout: Option<Duration>,
+    dns_server: Option<SocketAddr>,
+    resolve_overrides: HashMap<(String, u16), Vec<IpAddr>>,
+}
+
+impl Default for Prober {
+    fn default() -> Self {
+        Prober {
+            timeout: Duration::from_secs(5),
+            retries: 0,
+            backoff: Duration::from_millis(100),
+            concurrency: 50,
+            addresses: AddressMode::First,
+            dns_timeout: None,
+            dns_server: None,
+            resolve_overrides: HashMap::new(),
+        }
+    }
+}
+
+impl Prober {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Timeout per connection attempt.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Timeout for resolving a target's name; defaults to the connect timeout.
+    pub fn dns_timeout(mut self, timeout: Duration) -> Self {
+        self.dns_timeout = Some(timeout);
+        self
+    }
+
+    /// Send DNS queries to this server instead of using the system resolver.
+    pub fn dns_server(mut self, server: SocketAddr) -> Self {
+        self.dns_server = Some(server);
+        self
+    }
+
+    /// Pin `host:port` to `addr` without asking DNS, like curl's `--resolve`.
+    ///
+    /// The target keeps its host name for SNI and `Host` headers. Pinning the
+    /// same host and port again adds another address.
+    pub fn resolve_override(mut self, host: &str, port: u16, addr: IpAddr) -> Self {
+        self.resolve_overrides
+            .entry((host.to_ascii_lowercase(), port))
+            .or_default()
+            .push(addr);
+        self
+    }
+
+    /// Number of retries on failure.
+    pub fn retries(mut self, retries: u32) -> Self {
+        self.retries = retries;
+        self
+    }
+
+    /// Pause before the first retry; each further retry waits one step longer.
+    pub fn backoff(mut self, step: Duration) -> Self {
+        self.backoff = step;
+        self
+    }
+
+    /// Maximum number of probes in flight at once.
+    pub fn concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency = concurrency.max(1);
+        self
+    }
+
+    /// Which resolved addresses to probe; see [`AddressMode`].
+    pub fn addresses(mut self, mode: AddressMode) -> Self {
+        self.addresses = mode;
+        self
+    }
+
+    /// Look up the SRV records of `name` (e.g. `_postgres._tcp.db.example.com`),
+    /// sorted by priority and then by descending weight.
+    ///
+    /// Uses the [`dns_server`](Prober::dns_server) if set, otherwise the first
+    /// `nameserver` in `/etc/resolv.conf`.
+    pub async fn lookup_srv(&self, name: &str) -> Result<Vec<SrvRecord>, String> {
+        let server = match self.dns_server {
+            Some(server) => server,
+            None => dns::system_server()?,
+        };
+        let dns_timeout = self.dns_timeout.unwrap_or(self.timeout);
+        match timeout(dns_timeout, dns::lookup_srv(server, name)).await {
+            Ok(Ok(records)) if records.is_empty() => Err(format!("{}: no SRV records", name)),
+            Ok(Ok(records)) => Ok(records),
+            Ok(Err(e)) => Err(format!("DNS error: {} (server {})", e, server)),
+            Err(_) => Err(format!("DNS timeout ({}ms)", dns_timeout.as_millis())),
+        }
+    }
+
+    /// Probe a single target.
+    pub async fn probe(&self, target: impl Into<Target>) -> ProbeResult {
+        let target = target.into();
+        match self.addresses {
+            AddressMode::First => self.probe_first(&target).await,
+            AddressMode::HappyEyeballs => self.probe_racing(&target).await,
+            mode => self.probe_every_address(&target, mode).await,
+        }
+    }
+
+    /// Probe every target concurrently, returning results in input order.
+    pub async fn probe_many<I, T>(&self, targets: I) -> Summary
+    where
+        I: IntoIterator<Item = T>,
+        T: Into<Target>,
+    {
+        let semaphore = Arc::new(Semaphore::new(self.concurrency));
+        let mut handles = Vec::new();
+
+        for target in targets {
+            let sem = semaphore.clone();
+            let target = target.into();
+            let prober = self.clone();
+
+            handles.push((
+                target.clone(),
+                tokio::spawn(async move {
+                    let _permit = sem.acquire().await.unwrap();
+                    prober.probe(target).await
+                }),
+            ));
+        }
+
+        // One result per target, in order, so callers can match them up.
+        let total = handles.len();
+        let mut results = Vec::with_capacity(total);
+        for (target, handle) in handles {
+            results.push(match handle.await {
+                Ok(result) => result,
+                Err(e) => failure(&target, Status::Fail, Some(e.to_string()), 0),
+            });
+        }
+
+        Summary::new(results, total)
+    }
+}
+
+/// Probe `target`'s first resolved address, retrying on failure.
+pub async fn probe_host(target: &Target, connect_timeout: Duration, retries: u32) -> ProbeResult {
+    Prober::new()
+        .timeout(connect_timeout)
+        .retries(retries)
+        .probe(target.clone())
+        .await
+}
+
+impl Prober {
+    async fn probe_first(&self, target: &Target) -> ProbeResult {
+        let (connect_timeout, retries) = self.limits(target);
+        let mut started = Instant::now();
+        let mut dns_ms = None;
+        let mut last_error = None;
+        let mut last_status = Status::Fail;
+        let mut last_latency = None;
+        let mut retries_used = 0;
+
+        for attempt in 0..=retries {
+            if attempt > 0 {
+                retries_used = attempt;
+                tokio::time::sleep(self.backoff * attempt).await;
+                started = Instant::now();
+                dns_ms = None;
+                last_latency = None;
+            }
+
+            // Resolve DNS first
+            let addr = match self.resolve(target).await {
+                Ok(resolved) => {
+                    dns_ms = Some(resolved.ms());
+                    resolved.addrs[0]
+                }
+                Err(e) => {
+                    last_status = Status::Fail;
+                    last_error = Some(e);
+                    continue;
+                }
+            };
+
+            match connect_and_check(target, addr, connect_timeout).await {
+                Ok(connected) => {
+                    let result = success(target, connected, retries_used);
+                    return finish(result, dns_ms, started);
+                }
+                Err(failed) => {
+                    last_status = failed.status;
+                    last_error = Some(failed.error);
+                    last_latency = failed.latency;
+                }
+            }
+        }
+
+        let mut result = failure(target, last_status, last_error, retries_used);
+        result.timings.connect_ms = last_latency.map(millis);
+        finish(result, dns_ms, started)
+    }
+
+    /// Probe every address `target` resolves to, each with its own retries.
+    ///
+    /// With [`AddressMode::All`] any dead address fails the target; with
+    /// [`AddressMode::Any`] it only produces a warning as long as one address works.
+    async fn probe_every_address(&self, target: &Target, mode: AddressMode) -> ProbeResult {
+        let (connect_timeout, retries) = self.limits(target);
+        let started = Instant::now();
+        let mut resolved = Err(String::new());
+        let mut retries_used = 0;
+        for attempt in 0..=retries {
+            if attempt > 0 {
+                retries_used = attempt;
+                tokio::time::sleep(self.backoff * attempt).await;
+            }
+            resolved = self.resolve(target).await;
+            if resolved.is_ok() {
+                break;
+            }
+        }
+        let (addrs, dns_ms) = match resolved {
+            Ok(resolved) => (resolved.addrs.clone(), Some(resolved.ms())),
+            Err(e) => {
+                let result = failure(target, Status::Fail, Some(e), retries_used);
+                return finish(result, None, started);
+            }
+        };
+
+        let mut tasks = JoinSet::new();
+        for (index, addr) in addrs.iter().copied().enumerate() {
+            let target = target.clone();
+            let backoff = self.backoff;
+            tasks.spawn(async move {
+                let mut last = None;
+                for attempt in 0..=retries {
+                    if attempt > 0 {
+                        tokio::time::sleep(backoff * attempt).await;
+                    }
+                    match connect_and_check(&target, addr, connect_timeout).await {
+                        Ok(connected) => return (index, attempt, Ok(connected)),
+                        Err(failed) => last = Some(failed),
+                    }
+                }
+                (index, retries, Err(last.expect("at least one attempt")))
+            });
+        }
+
+        let mut outcomes: Vec<Option<Result<Connected, Failed>>> =
+            addrs.iter().map(|_| None).collect();
+        while let Some(joined) = tasks.join_next().await {
+            if let Ok((index, attempts, outcome)) = joined {
+                retries_used = retries_used.max(attempts);
+                outcomes[index] = Some(outcome);
+            }
+        }
+
+        let mut addresses = Vec::with_capacity(addrs.len());
+        let mut best: Option<Connected> = None;
+        let mut dead = Vec::new();
+        for (addr, outcome) in addrs.iter().zip(outcomes) {
+            let outcome =
+                outcome.unwrap_or_else(|| Err(Failed::new("probe task failed".to_string())));
+            match outcome {
+                Ok(connected) => {
+                    addresses.push(AddressResult {
+                        addr: addr.to_string(),
+                        status: connected.status(),
+                        latency_ms: Some(millis(connected.latency)),
+                        error: None,
+                    });
+                    if best.as_ref().is_none_or(|b| connected.latency < b.latency) {
+                        best = Some(connected);
+                    }
+                }
+                Err(failed) => {
+                    dead.push(format!("{} {}", addr, failed.error));
+                    addresses.push(AddressResult {
+                        addr: addr.to_string(),
+                        status: failed.status,
+                        latency_ms: None,
+                        error: Some(failed.error),
+                    });
+                }
+            }
+        }
+
+        let summary = format!(
+            "{}/{} addresses failed: {}",
+            dead.len(),
+            addrs.len(),
+            dead.join("; ")
+        );
+        let mut result = match best {
+            Some(connected) if dead.is_empty() || mode == AddressMode::Any => {
+                let mut result = success(target, connected, retries_used);
+                if !dead.is_empty() {
+                    result.status = Status::Warn;
+                    result.warning = Some(summary);
+                }
+                result
+            }
+            _ => {
+                let status = addresses
+                    .iter()
+                    .map(|a| a.status)
+                    .find(|s| !s.is_healthy())
+                    .unwrap_or(Status::Fail);
+                failure(target, status, Some(summary), retries_used)
+            }
+        };
+        result.addresses = addresses;
+        finish(result, dns_ms, started)
+    }
+
+    /// Race the target's addresses per RFC 8305 and run the check on the winner.
+    async fn probe_racing(&self, target: &Target) -> ProbeResult {
+        let (connect_timeout, retries) = self.limits(target);
+        let mut started = Instant::now();
+        let mut dns_ms = None;
+        let mut last_error = None;
+        let mut last_status = Status::Fail;
+        let mut last_latency = None;
+        let mut last_race = None;
+        let mut retries_used = 0;
+
+        for attempt in 0..=retries {
+            if attempt > 0 {
+                retries_used = attempt;
+                tokio::time::sleep(self.backoff * attempt).await;
+                started = Instant::now();
+                dns_ms = None;
+                last_latency = None;
+            }
+
+            let addrs = match self.resolve(target).await {
+                Ok(resolved) => {
+                    dns_ms = Some(resolved.ms());
+                    resolved.addrs
+                }
+                Err(e) => {
+                    last_status = Status::Fail;
+                    last_error = Some(e);
+                    continue;
+                }
+            };
+
+            let start = Instant::now();
+            let (winner, race) =
+                happy_eyeballs::race(&addrs, connect_timeout, CONNECTION_ATTEMPT_DELAY).await;
+            let Some((addr, stream)) = winner else {
+                last_status = Status::Fail;
+                last_error = Some(format!("all {} connection attempts failed", addrs.len()));
+                last_race = Some(race);
+                continue;
+            };
+
+            let mut result =
+                match check_stream(target, stream, addr, start.elapsed(), connect_timeout).await {
+                    Ok(connected) => success(target, connected, retries_used),
+                    Err(failed) => {
+                        last_status = failed.status;
+                        last_error = Some(failed.error);
+                        last_latency = failed.latency;
+                        last_race = Some(race);
+                        continue;
+                    }
+                };
+            result.happy_eyeballs = Some(race);
+            return finish(result, dns_ms, started);
+        }
+
+        let mut result = failure(target, last_status, last_error, retries_used);
+        result.timings.connect_ms = last_latency.map(millis);
+        result.happy_eyeballs = last_race;
+        finish(result, dns_ms, started)
+    }
+
+    /// Connect timeout and retries for `target`, which may override the prober's.
+    fn limits(&self, target: &Target) -> (Duration, u32) {
+        (
+            target.timeout.unwrap_or(self.timeout),
+            target.retries.unwrap_or(self.retries),
+        )
+    }
+
+    /// Resolve the target's `host:port` without blocking the runtime, failing if
+    /// the name has no addresses.
+    ///
+    /// Pinned addresses win over DNS; otherwise the configured DNS server, or
+    /// else the system resolver, is asked.
+    async fn resolve(&self, target: &Target) -> Result<Resolved, String> {
+        let start = Instant::now();
+        let host = target.host();
+        let Some(port) = target.port() else {
+            return Err(format!("missing port in '{}'", target.addr));
+        };
+
+        let pinned = self
+            .resolve_overrides
+            .get(&(host.to_ascii_lowercase(), port));
+        if let Some(ips) = pinned {
+            return Ok(Resolved {
+                addrs: ips.iter().map(|&ip| SocketAddr::new(ip, port)).collect(),
+                elapsed: start.elapsed(),
+            });
+        }
+
+        let dns_timeout = self.dns_timeout.unwrap_or(self.limits(target).0);
+        let lookup = async {
+            match (self.dns_server, host.parse::<IpAddr>()) {
+                (Some(server), Err(_)) => {
+                    let ips = dns::lookup(server, host)
+                        .await
+                        .map_err(|e| format!("DNS error: {} (server {})", e, server))?;
+                    Ok(ips
+                        .into_iter()
+                        .map(|ip| SocketAddr::new(ip, port))
+                        .collect())
+                }
+                _ => match lookup_host(target.addr.as_str()).await {
+                    Ok(addrs) => Ok(addrs.collect()),
+                    Err(e) => Err(format!("DNS error: {}", e)),
+                },
+            }
+        };
+        let addrs: Vec<SocketAddr> = match timeout(dns_timeout, lookup).await {
+            Ok(result) => result?,
+            Err(_) => return Err(format!("DNS timeout ({}ms)", dns_timeout.as_millis())),
+        };
+        if addrs.is_empty() {
+            return Err("DNS resolution failed: no addresses".to_string());
+        }
+        Ok(Resolved {
+            addrs,
+            elapsed: start.elapsed(),
+        })
+    }
+}
+
+struct Resolved {
+    addrs: Vec<SocketAddr>,
+    elapsed: Duration,
+}
+
+impl Resolved {
+    fn ms(&self) -> f64 {
+        millis(self.elapsed)
+    }
+}
+
+fn millis(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1000.0
+}
+
+/// A connection that was established and passed its check.
+struct Connected {
+    latency: Duration,
+    outcome: CheckOutcome,
+}
+
+impl Connected {
+    fn status(&self) -> Status {
+        if self.outcome.warning.is_some() {
+            Status::Warn
+        } else {
+            Status::Ok
+        }
+    }
+}
+
+struct Failed {
+    status: Status,
+    error: String,
+    /// Connect time, when the failure came from the check.
+    latency: Option<Duration>,
+}
+
+impl Failed {
+    fn new(error: String) -> Self {
+        Failed {
+            status: Status::Fail,
+            error,
+            latency: None,
+        }
+    }
+}
+
+async fn connect_and_check(
+    target: &Target,
+    addr: SocketAddr,
+    connect_timeout: Duration,
+) -> Result<Connected, Failed> {
+    let start = Instant::now();
+    let stream = match timeout(connect_timeout, TcpStream::connect(addr)).await {
+        Ok(Ok(stream)) => stream,
+        Ok(Err(e)) => return Err(Failed::new(format!("Connection refused: {}", e))),
+        Err(_) => {
+            return Err(Failed::new(format!(
+                "timeout ({}ms)",
+                connect_timeout.as_millis()
+            )))
+        }
+    };
+    check_stream(target, stream, addr, start.elapsed(), connect_timeout).await
+}
+
+/// Run the target's check, if any, on an established connection.
+async fn check_stream(
+    target: &Target,
+    mut stream: TcpStream,
+    addr: SocketAddr,
+    latency: Duration,
+    connect_timeout: Duration,
+) -> Result<Connected, Failed> {
+    let Some(check) = &target.check else {
+        return Ok(Connected {
+            latency,
+            outcome: CheckOutcome::new(),
+        });
+    };
+
+    let ctx = CheckContext {
+        host: target.host(),
+        peer: addr,
+        timeout: connect_timeout,
+        started: Instant::now(),
+    };
+    match timeout(connect_timeout + CHECK_GRACE, check.run(&mut stream, &ctx)).await {
+        Ok(Ok(outcome)) => Ok(Connected { latency, outcome }),
+        Ok(Err(e)) => Err(Failed {
+            status: e.status(),
+            error: format!("{} check failed: {}", check.name(), e),
+            latency: Some(latency),
+        }),
+        Err(_) => Err(Failed {
+            latency: Some(latency),
+            ..Failed::new(format!(
+                "{} check timeout ({}ms)",
+                check.name(),
+                connect_timeout.as_millis()
+            ))
+        }),
+    }
+}
+
+fn success(target: &Target, connected: Connected, retries_used: u32) -> ProbeResult {
+    let status = connected.status();
+    let outcome = connected.outcome;
+    let latency_ms = millis(connected.latency);
+    ProbeResult {
+        host: target.name().to_string(),
+        description: target.description.clone(),
+        tags: target.tags.clone(),
+        status,
+        latency_ms: Some(latency_ms),
+        error: None,
+        warning: outcome.warning,
+        retries_used,
+        timings: Timings {
+            connect_ms: Some(latency_ms),
+            tls_ms: outcome.tls.as_ref().map(|tls| tls.handshake_ms),
+            first_byte_ms: outcome.first_byte.map(millis),
+            ..Timings::default()
+        },
+        check: target.check.as_ref().map(|c| c.name().to_string()),
+        banner: outcome.banner,
+        tls: outcome.tls,
+        http: outcome.http,
+        addresses: Vec::new(),
+        happy_eyeballs: None,
+        members: Vec::new(),
+        details: outcome.details,
+    }
+}
+
+/// Fill in the timings only the caller knows: name resolution and the whole attempt.
+fn finish(mut result: ProbeResult, dns_ms: Option<f64>, started: Instant) -> ProbeResult {
+    result.timings.dns_ms = dns_ms;
+    result.timings.total_ms = millis(started.elapsed());
+    result
+}
+
+fn failure(
+    target: &Target,
+    status: Status,
+    error: Option<String>,
+    retries_used: u32,
+) -> ProbeResult {
+    ProbeResult {
+        host: target.name().to_string(),
+        description: target.description.clone(),
+        tags: target.tags.clone(),
+        status,
+        latency_ms: None,
+        error,
+        warning: None,
+        retries_used,
+        timings: Timings::default(),
+        check: target.check.as_ref().map(|c| c.name().to_string()),
+        banner: None,
+        tls: None,
+        http: None,
+        addresses: Vec::new(),
+        happy_eyeballs: None,
+        members: Vec::new(),
+        details: Default::default(),
+    }
+}
@@ -0,0 +1,169 @@
+use serde::Serialize;
+use serde_json::{Map, Value};
+
+/// Outcome of a single probe.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Status {
+    Ok,
+    /// Reachable and passing, but something needs attention (e.g. a certificate close to expiry).
+    Warn,
+    /// Answering, but not ready yet (e.g. a database still starting up).
+    Starting,
+    Fail,
+}
+
+impl Status {
+    pub fn is_healthy(self) -> bool {
+        matches!(self, Status::Ok | Status::Warn)
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ProbeResult {
+    pub host: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    pub status: Status,
+    pub latency_ms: Option<f64>,
+    pub error: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub warning: Option<String>,
+    pub retries_used: u32,
+    /// Where the time of the last attempt went.
+    pub timings: Timings,
+    /// Name of the protocol check that ran after connecting, if any.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub check: Option<String>,
+    /// Greeting captured after connecting, with non-printable bytes escaped.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub banner: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tls: Option<TlsInfo>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub http: Option<HttpInfo>,
+    /// Per-address results when every resolved address is probed.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub addresses: Vec<AddressResult>,
+    /// Connection race details in Happy Eyeballs mode.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub happy_eyeballs: Option<RaceResult>,
+    /// Results of the targets a group (an SRV name, several ports, ...) expanded to.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub members: Vec<ProbeResult>,
+    /// Extra data reported by the check.
+    #[serde(skip_serializing_if = "Map::is_empty")]
+    pub details: Map<String, Value>,
+}
+
+/// Result for one resolved address of a target.
+#[derive(Debug, Clone, Serialize)]
+pub struct AddressResult {
+    pub addr: String,
+    pub status: Status,
+    pub latency_ms: Option<f64>,
+    pub error: Option<String>,
+}
+
+/// Outcome of a Happy Eyeballs (RFC 8305) connection race.
+#[derive(Debug, Clone, Serialize)]
+pub struct RaceResult {
+    /// Address whose connection won the race.
+    pub winner: Option<String>,
+    /// `"ipv6"` or `"ipv4"`.
+    pub family: Option<&'static str>,
+    /// Attempts in the order they were (or would have been) started.
+    pub attempts: Vec<RaceAttempt>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RaceAttempt {
+    pub addr: String,
+    pub family: &'static str,
+    /// When the attempt started, relative to the start of the race.
+    pub started_ms: Option<f64>,
+    /// How long the attempt ran before it connected, failed or was cancelled.
+    pub elapsed_ms: Option<f64>,
+    /// `won`, `failed`, `cancelled` or `not_started`.
+    pub outcome: &'static str,
+    pub error: Option<String>,
+}
+
+/// Negotiated TLS session and leaf certificate details.
+#[derive(Debug, Clone, Serialize)]
+pub struct TlsInfo {
+    /// Time spent in the TLS handshake, excluding the TCP connect.
+    pub handshake_ms: f64,
+    pub version: String,
+    pub cipher: String,
+    pub alpn: Option<String>,
+    pub subject: Option<String>,
+    pub sans: Vec<String>,
+    /// Leaf certificate expiry (RFC 3339).
+    pub not_after: Option<String>,
+    pub days_left: Option<i64>,
+}
+
+/// Response to an HTTP(S) check.
+#[derive(Debug, Clone, Serialize)]
+pub struct HttpInfo {
+    pub status: u16,
+    /// Time from sending the request to the first response byte.
+    pub ttfb_ms: f64,
+    /// Time from sending the request to the end of the body.
+    pub total_ms: f64,
+    pub body_bytes: usize,
+}
+
+impl ProbeResult {
+    pub fn is_healthy(&self) -> bool {
+        self.status.is_healthy()
+    }
+}
+
+/// Phase breakdown of the last attempt, in the spirit of curl's `-w` timers.
+///
+/// Unlike curl's cumulative timers each field covers only its own phase;
+/// phases that did not run (no TLS, a check that never read) are `None`.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct Timings {
+    /// Name resolution.
+    pub dns_ms: Option<f64>,
+    /// TCP handshake (same as `latency_ms`).
+    pub connect_ms: Option<f64>,
+    /// TLS handshake, including STARTTLS upgrades.
+    pub tls_ms: Option<f64>,
+    /// From the established connection to the first byte of the server's response.
+    pub first_byte_ms: Option<f64>,
+    /// The whole attempt, from resolving the name to the end of the check.
+    pub total_ms: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Summary {
+    pub results: Vec<ProbeResult>,
+    pub healthy: usize,
+    /// Healthy targets that came back with a warning.
+    pub warnings: usize,
+    pub total: usize,
+}
+
+impl Summary {
+    pub fn new(results: Vec<ProbeResult>, total: usize) -> Self {
+        let healthy = results.iter().filter(|r| r.is_healthy()).count();
+        let warnings = results.iter().filter(|r| r.status == Status::Warn).count();
+        Summary {
+            results,
+            healthy,
+            warnings,
+            total,
+        }
+    }
+
+    /// True when every target that was probed came back healthy.
+    pub fn all_healthy(&self) -> bool {
+        self.healthy == self.total
+    }
+}
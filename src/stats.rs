@@ -0,0 +1,49 @@
+//! Running latency statistics for repeated probes.
+
+/// Min, average, max and mean deviation of a series of latencies, kept
+/// without storing the samples.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Latency {
+    count: u64,
+    min: f64,
+    max: f64,
+    sum: f64,
+    sum_squares: f64,
+}
+
+impl Latency {
+    pub fn add(&mut self, ms: f64) {
+        if self.count == 0 {
+            (self.min, self.max) = (ms, ms);
+        } else {
+            self.min = self.min.min(ms);
+            self.max = self.max.max(ms);
+        }
+        self.count += 1;
+        self.sum += ms;
+        self.sum_squares += ms * ms;
+    }
+
+    fn is_empty(&self) -> bool {
+        self.count == 0
+    }
+
+    pub fn min(&self) -> Option<f64> {
+        (!self.is_empty()).then_some(self.min)
+    }
+
+    pub fn max(&self) -> Option<f64> {
+        (!self.is_empty()).then_some(self.max)
+    }
+
+    pub fn avg(&self) -> Option<f64> {
+        (!self.is_empty()).then(|| self.sum / self.count as f64)
+    }
+
+    /// Standard deviation, which `ping` calls mdev.
+    pub fn mdev(&self) -> Option<f64> {
+        let avg = self.avg()?;
+        let variance = self.sum_squares / self.count as f64 - avg * avg;
+        Some(variance.max(0.0).sqrt())
+    }
+}
@@ -0,0 +1,115 @@
+use std::fmt;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::check::Check;
+
+/// A `host:port` to probe, optionally with a protocol check to run after connecting.
+#[derive(Clone)]
+pub struct Target {
+    pub addr: String,
+    /// Name shown in results instead of `addr`, e.g. the URL the target came from.
+    pub label: Option<String>,
+    pub check: Option<Arc<dyn Check>>,
+    /// Connect timeout for this target instead of the prober's.
+    pub timeout: Option<Duration>,
+    /// Retries for this target instead of the prober's.
+    pub retries: Option<u32>,
+    /// Free-form labels copied to the result, e.g. `["db", "prod"]`.
+    pub tags: Vec<String>,
+    /// Note copied to the result.
+    pub description: Option<String>,
+}
+
+impl Target {
+    pub fn new(addr: impl Into<String>) -> Self {
+        Target {
+            addr: addr.into(),
+            label: None,
+            check: None,
+            timeout: None,
+            retries: None,
+            tags: Vec::new(),
+            description: None,
+        }
+    }
+
+    pub fn with_check(mut self, check: impl Check + 'static) -> Self {
+        self.check = Some(Arc::new(check));
+        self
+    }
+
+    pub fn with_label(mut self, label: impl Into<String>) -> Self {
+        self.label = Some(label.into());
+        self
+    }
+
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    pub fn with_retries(mut self, retries: u32) -> Self {
+        self.retries = Some(retries);
+        self
+    }
+
+    pub fn with_tags<I, S>(mut self, tags: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.tags = tags.into_iter().map(Into::into).collect();
+        self
+    }
+
+    pub fn with_description(mut self, description: impl Into<String>) -> Self {
+        self.description = Some(description.into());
+        self
+    }
+
+    /// Name to report results under.
+    pub fn name(&self) -> &str {
+        self.label.as_deref().unwrap_or(&self.addr)
+    }
+
+    /// Host part of `addr`, without the port or IPv6 brackets.
+    pub fn host(&self) -> &str {
+        let host = match self.addr.rsplit_once(':') {
+            Some((host, _)) => host,
+            None => &self.addr,
+        };
+        host.trim_start_matches('[').trim_end_matches(']')
+    }
+
+    /// Port part of `addr`, if it has a numeric one.
+    pub fn port(&self) -> Option<u16> {
+        self.addr.rsplit_once(':')?.1.parse().ok()
+    }
+}
+
+impl fmt::Debug for Target {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Target")
+            .field("addr", &self.addr)
+            .field("label", &self.label)
+            .field("check", &self.check.as_ref().map(|c| c.name()))
+            .field("timeout", &self.timeout)
+            .field("retries", &self.retries)
+            .field("tags", &self.tags)
+            .field("description", &self.description)
+            .finish()
+    }
+}
+
+impl From<&str> for Target {
+    fn from(addr: &str) -> Self {
+        Target::new(addr)
+    }
+}
+
+impl From<String> for Target {
+    fn from(addr: String) -> Self {
+        Target::new(addr)
+    }
+}
@@ -0,0 +1,283 @@
+//! `scheme://[user[:password]@]host[:port][/path]` targets.
+
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TargetUrl {
+    pub scheme: String,
+    /// Percent-decoded user name.
+    pub user: Option<String>,
+    /// Percent-decoded password.
+    pub password: Option<String>,
+    pub host: String,
+    pub port: Option<u16>,
+    /// Path plus query string, `/` when the URL has none.
+    pub path: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UrlError(String);
+
+impl fmt::Display for UrlError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for UrlError {}
+
+impl TargetUrl {
+    /// True if `s` looks like a URL rather than a `host:port`.
+    pub fn is_url(s: &str) -> bool {
+        s.contains("://")
+    }
+
+    pub fn parse(s: &str) -> Result<Self, UrlError> {
+        let err = |msg: &str| UrlError(format!("invalid URL '{}': {}", s, msg));
+
+        let (scheme, rest) = s.split_once("://").ok_or_else(|| err("missing scheme"))?;
+        if scheme.is_empty()
+            || !scheme
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || "+-.".contains(c))
+        {
+            return Err(err("bad scheme"));
+        }
+
+        let (authority, path) = match rest.find(['/', '?', '#']) {
+            Some(i) => (&rest[..i], &rest[i..]),
+            None => (rest, ""),
+        };
+        let path = path.split('#').next().unwrap_or_default();
+        let path = if path.starts_with('/') {
+            path.to_string()
+        } else {
+            format!("/{}", path)
+        };
+
+        let (userinfo, hostport) = match authority.rsplit_once('@') {
+            Some((userinfo, hostport)) => (Some(userinfo), hostport),
+            None => (None, authority),
+        };
+        let decode = |part: &str| percent_decode(part).map_err(|msg| err(&msg));
+        let (user, password) = match userinfo {
+            Some(info) => match info.split_once(':') {
+                Some((user, password)) => (Some(decode(user)?), Some(decode(password)?)),
+                None => (Some(decode(info)?), None),
+            },
+            None => (None, None),
+        };
+
+        let (host, port) = if let Some(bracketed) = hostport.strip_prefix('[') {
+            let (host, after) = bracketed
+                .split_once(']')
+                .ok_or_else(|| err("unclosed '['"))?;
+            (host, after.strip_prefix(':'))
+        } else {
+            match hostport.rsplit_once(':') {
+                Some((host, port)) => (host, Some(port)),
+                None => (hostport, None),
+            }
+        };
+        if host.is_empty() {
+            return Err(err("missing host"));
+        }
+        let port = match port {
+            Some(p) => Some(p.parse::<u16>().map_err(|_| err("bad port"))?),
+            None => None,
+        };
+
+        Ok(TargetUrl {
+            scheme: scheme.to_ascii_lowercase(),
+            user,
+            password,
+            host: host.to_string(),
+            port,
+            path,
+        })
+    }
+
+    /// First path segment, percent-decoded, e.g. the database of
+    /// `postgres://db/app?sslmode=require`.
+    pub fn database(&self) -> Result<Option<String>, UrlError> {
+        let path = self.path.split('?').next().unwrap_or_default();
+        match path.trim_start_matches('/').split('/').next() {
+            Some(name) if !name.is_empty() => percent_decode(name)
+                .map(Some)
+                .map_err(|msg| UrlError(format!("invalid database in URL: {}", msg))),
+            _ => Ok(None),
+        }
+    }
+
+    /// Well-known port of the URL's scheme, e.g. 5432 for `postgres://`.
+    pub fn default_port(&self) -> Option<u16> {
+        let port = match self.scheme.as_str() {
+            "http" => 80,
+            "https" => 443,
+            "postgres" | "postgresql" => 5432,
+            "redis" => 6379,
+            "mysql" | "mariadb" => 3306,
+            "ssh" => 22,
+            "smtp" => 25,
+            _ => return None,
+        };
+        Some(port)
+    }
+
+    /// The URL with any password replaced by `***`, for display.
+    pub fn redacted(&self) -> String {
+        let mut shown = format!("{}://", self.scheme);
+        if let Some(user) = &self.user {
+            shown.push_str(user);
+            if self.password.is_some() {
+                shown.push_str(":***");
+            }
+            shown.push('@');
+        }
+        if self.host.contains(':') {
+            shown.push_str(&format!("[{}]", self.host));
+        } else {
+            shown.push_str(&self.host);
+        }
+        if let Some(port) = self.port {
+            shown.push_str(&format!(":{}", port));
+        }
+        if self.path != "/" {
+            shown.push_str(&self.path);
+        }
+        shown
+    }
+
+    /// `host:port` to connect to, using `default_port` when the URL has none.
+    pub fn addr(&self, default_port: u16) -> String {
+        let port = self.port.unwrap_or(default_port);
+        if self.host.contains(':') {
+            format!("[{}]:{}", self.host, port)
+        } else {
+            format!("{}:{}", self.host, port)
+        }
+    }
+}
+
+/// Decode `%HH` escapes, as used for special characters in credentials.
+fn percent_decode(s: &str) -> Result<String, String> {
+    let mut out = Vec::with_capacity(s.len());
+    let mut bytes = s.bytes();
+    while let Some(b) = bytes.next() {
+        if b != b'%' {
+            out.push(b);
+            continue;
+        }
+        let hex: Vec<u8> = bytes.by_ref().take(2).collect();
+        let byte = std::str::from_utf8(&hex)
+            .ok()
+            .filter(|hex| hex.len() == 2)
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+            .ok_or_else(|| format!("bad percent-encoding in '{}'", s))?;
+        out.push(byte);
+    }
+    String::from_utf8(out).map_err(|_| format!("percent-encoding in '{}' is not UTF-8", s))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_bracketed_ipv6_hosts() {
+        let url = TargetUrl::parse("http://[::1]:8080/health").unwrap();
+        assert_eq!((url.host.as_str(), url.port), ("::1", Some(8080)));
+        assert_eq!(url.addr(80), "[::1]:8080");
+
+        let url = TargetUrl::parse("redis://[fe80::1]").unwrap();
+        assert_eq!((url.host.as_str(), url.port), ("fe80::1", None));
+        assert_eq!(url.addr(6379), "[fe80::1]:6379");
+
+        assert!(TargetUrl::parse("http://[::1/").is_err());
+    }
+
+    #[test]
+    fn parses_password_without_user() {
+        let url = TargetUrl::parse("redis://:secret@cache").unwrap();
+        assert_eq!(url.user.as_deref(), Some(""));
+        assert_eq!(url.password.as_deref(), Some("secret"));
+        assert_eq!(url.host, "cache");
+    }
+
+    #[test]
+    fn decodes_percent_escapes_in_credentials() {
+        let url = TargetUrl::parse("postgres://app%2Bro:p%40ss%3Aw0rd@db/app").unwrap();
+        assert_eq!(url.user.as_deref(), Some("app+ro"));
+        assert_eq!(url.password.as_deref(), Some("p@ss:w0rd"));
+        assert_eq!(url.host, "db");
+    }
+
+    #[test]
+    fn rejects_bad_percent_escapes() {
+        for input in [
+            "redis://:pw%4@cache",
+            "redis://:pw%zz@cache",
+            "redis://:%ff@cache",
+        ] {
+            assert!(
+                TargetUrl::parse(input).is_err(),
+                "{:?} should be rejected",
+                input
+            );
+        }
+    }
+
+    #[test]
+    fn keeps_query_in_path() {
+        assert_eq!(TargetUrl::parse("http://h?x=1").unwrap().path, "/?x=1");
+        assert_eq!(TargetUrl::parse("http://h#top").unwrap().path, "/");
+        assert_eq!(TargetUrl::parse("http://h/a?b#c").unwrap().path, "/a?b");
+    }
+
+    #[test]
+    fn database_is_first_path_segment() {
+        let database = |s: &str| TargetUrl::parse(s).unwrap().database();
+        assert_eq!(
+            database("postgres://db/app?sslmode=require"),
+            Ok(Some("app".to_string()))
+        );
+        assert_eq!(
+            database("postgres://db/my%20app"),
+            Ok(Some("my app".to_string()))
+        );
+        assert_eq!(database("postgres://db"), Ok(None));
+        assert_eq!(database("postgres://db/?sslmode=require"), Ok(None));
+        assert!(database("postgres://db/a%zz").is_err());
+    }
+
+    #[test]
+    fn rejects_bad_hosts_ports_and_schemes() {
+        for input in [
+            "db:5432",
+            "://db",
+            "po stgres://db",
+            "postgres://",
+            "postgres://user@:5432",
+            "postgres://db:99999",
+            "postgres://db:port",
+        ] {
+            assert!(
+                TargetUrl::parse(input).is_err(),
+                "{:?} should be rejected",
+                input
+            );
+        }
+    }
+
+    #[test]
+    fn redacted_hides_the_password() {
+        let url = TargetUrl::parse("postgres://app:s3cret@[::1]:6432/app").unwrap();
+        assert_eq!(url.redacted(), "postgres://app:***@[::1]:6432/app");
+        assert!(!url.redacted().contains("s3cret"));
+
+        let url = TargetUrl::parse("redis://:s3cret@cache").unwrap();
+        assert_eq!(url.redacted(), "redis://:***@cache");
+        let url = TargetUrl::parse("https://api.example.com").unwrap();
+        assert_eq!(url.redacted(), "https://api.example.com");
+    }
+}
@@ -0,0 +1,236 @@
+//! `--watch`: probe the same targets at an interval and report only when one
+//! goes up or down, with uptime and latency statistics on exit.
+
+use colored::Colorize;
+use serde_json::json;
+use std::time::{Duration, Instant};
+use tcp_probe::duration;
+use tcp_probe::{ProbeResult, Prober, Target};
+use time::format_description::well_known::Rfc3339;
+use time::OffsetDateTime;
+use tokio::time::MissedTickBehavior;
+
+use crate::stats::Latency;
+use crate::{probe_entries, srv_entry, status_label, Args, Entry};
+
+/// What one target has done since watching started.
+struct History {
+    host: String,
+    probes: u64,
+    up: u64,
+    /// Latency of the healthy probes.
+    latency: Latency,
+    changes: u64,
+    /// Current state and when it began.
+    state: Option<(bool, Instant)>,
+}
+
+impl History {
+    fn new(host: String) -> Self {
+        History {
+            host,
+            probes: 0,
+            up: 0,
+            latency: Latency::default(),
+            changes: 0,
+            state: None,
+        }
+    }
+
+    /// Record one result, returning how long the previous state lasted if
+    /// this one differs from it (`Some(None)` for the first result).
+    fn record(&mut self, result: &ProbeResult) -> Option<Option<Duration>> {
+        let healthy = result.is_healthy();
+        self.probes += 1;
+        if healthy {
+            self.up += 1;
+            if let Some(ms) = result.latency_ms {
+                self.latency.add(ms);
+            }
+        }
+        match self.state {
+            Some((was, _)) if was == healthy => None,
+            Some((_, since)) => {
+                self.changes += 1;
+                self.state = Some((healthy, Instant::now()));
+                Some(Some(since.elapsed()))
+            }
+            None => {
+                self.state = Some((healthy, Instant::now()));
+                Some(None)
+            }
+        }
+    }
+
+    fn uptime(&self) -> f64 {
+        self.up as f64 * 100.0 / self.probes.max(1) as f64
+    }
+
+    fn healthy(&self) -> bool {
+        matches!(self.state, Some((true, _)))
+    }
+}
+
+/// Probe `entries` every `interval` until `--count` rounds have run or Ctrl-C
+/// is pressed. Returns whether every target was healthy in the last round.
+///
+/// `targets` are what `entries` were built from, one for one; `srv:` targets
+/// are looked up again every round so record changes and lookups that start
+/// (or stop) failing show up while watching.
+pub async fn run(
+    prober: &Prober,
+    targets: &[(String, Target)],
+    mut entries: Vec<Entry>,
+    args: &Args,
+    interval: Duration,
+) -> bool {
+    let json = args.json;
+    let started = Instant::now();
+    let mut history: Vec<History> = Vec::new();
+    let mut rounds = 0;
+    let mut ticker = tokio::time::interval(interval);
+    ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+    while args.count.is_none_or(|count| rounds < count) {
+        let summary = tokio::select! {
+            _ = tokio::signal::ctrl_c() => break,
+            summary = async {
+                ticker.tick().await;
+                if rounds > 0 {
+                    refresh_srv(prober, targets, &mut entries, args).await;
+                }
+                probe_entries(prober, entries.clone()).await
+            } => summary,
+        };
+        rounds += 1;
+        if history.is_empty() {
+            history = summary
+                .results
+                .iter()
+                .map(|r| History::new(r.host.clone()))
+                .collect();
+        }
+        for (result, history) in summary.results.iter().zip(&mut history) {
+            if let Some(previous) = history.record(result) {
+                print_change(result, previous, json);
+            }
+        }
+    }
+
+    print_stats(&history, rounds, started.elapsed(), json);
+    history.iter().all(History::healthy)
+}
+
+async fn refresh_srv(
+    prober: &Prober,
+    targets: &[(String, Target)],
+    entries: &mut [Entry],
+    args: &Args,
+) {
+    for ((addr, template), entry) in targets.iter().zip(entries) {
+        if let Some(name) = addr.strip_prefix("srv:") {
+            *entry = srv_entry(prober, name, template, args).await;
+        }
+    }
+}
+
+/// Current UTC time to the second, e.g. `2026-10-18T09:00:00Z`.
+pub fn timestamp() -> String {
+    let now = OffsetDateTime::now_utc();
+    now.replace_nanosecond(0)
+        .unwrap_or(now)
+        .format(&Rfc3339)
+        .unwrap_or_default()
+}
+
+/// One line for a target that came up or went down; `previous` is how long
+/// it had been in the other state.
+fn print_change(result: &ProbeResult, previous: Option<Duration>, json: bool) {
+    if json {
+        let mut event = serde_json::to_value(result).unwrap();
+        event["timestamp"] = timestamp().into();
+        println!("{}", event);
+        return;
+    }
+
+    let detail = match (result.is_healthy(), result.latency_ms) {
+        (true, Some(ms)) => format!("{:.1}ms", ms),
+        (true, None) => String::new(),
+        (false, _) => result
+            .error
+            .as_deref()
+            .unwrap_or("unknown")
+            .red()
+            .to_string(),
+    };
+    let previous = match previous {
+        Some(d) if result.is_healthy() => format!("  (down for {})", duration::format(d)),
+        Some(d) => format!("  (up for {})", duration::format(d)),
+        None => String::new(),
+    };
+    println!(
+        "{}  {} {:<30} {}{}",
+        timestamp().dimmed(),
+        status_label(result.status),
+        result.host,
+        detail,
+        previous.dimmed()
+    );
+}
+
+fn print_stats(history: &[History], rounds: u64, elapsed: Duration, json: bool) {
+    if json {
+        let targets: Vec<_> = history
+            .iter()
+            .map(|h| {
+                json!({
+                    "host": h.host,
+                    "probes": h.probes,
+                    "up": h.up,
+                    "uptime_pct": h.uptime(),
+                    "changes": h.changes,
+                    "latency_min_ms": h.latency.min(),
+                    "latency_avg_ms": h.latency.avg(),
+                    "latency_max_ms": h.latency.max(),
+                })
+            })
+            .collect();
+        let stats = json!({
+            "rounds": rounds,
+            "elapsed_ms": elapsed.as_secs_f64() * 1000.0,
+            "targets": targets,
+        });
+        println!("{}", stats);
+        return;
+    }
+
+    println!(
+        "\n{}: {} rounds in {}",
+        "Summary".bold(),
+        rounds,
+        duration::format(elapsed)
+    );
+    for h in history {
+        let uptime = format!("{:>6.2}%", h.uptime());
+        let uptime = if h.up == h.probes {
+            uptime.green()
+        } else {
+            uptime.red()
+        };
+        let latency = match (h.latency.min(), h.latency.avg(), h.latency.max()) {
+            (Some(min), Some(avg), Some(max)) => {
+                format!("min {:.1}ms avg {:.1}ms max {:.1}ms", min, avg, max)
+            }
+            _ => "-".to_string(),
+        };
+        let changes = match h.changes {
+            0 => String::new(),
+            1 => "  1 change".to_string(),
+            n => format!("  {} changes", n),
+        };
+        println!(
+            "  {:<30} {} up ({}/{})  {}{}",
+            h.host, uptime, h.up, h.probes, latency, changes
+        );
+    }
+}